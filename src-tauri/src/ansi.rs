@@ -0,0 +1,184 @@
+use serde::Serialize;
+
+// region: Variables
+
+/// Standard and bright colors (xterm defaults), indexed by SGR color number.
+const PALETTE: [&str; 16] = [
+    "#000000", "#cd0000", "#00cd00", "#cdcd00", "#0000ee", "#cd00cd", "#00cdcd", "#e5e5e5",
+    "#7f7f7f", "#ff0000", "#00ff00", "#ffff00", "#5c5cff", "#ff00ff", "#00ffff", "#ffffff",
+];
+
+// endregion
+
+// region: Structs
+
+/// Run of text sharing the same style. Colors are `#rrggbb`.
+#[derive(Debug, Serialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Span {
+    pub text: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fg: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bg: Option<String>,
+    #[serde(skip_serializing_if = "is_false")]
+    pub bold: bool,
+    #[serde(skip_serializing_if = "is_false")]
+    pub italic: bool,
+    #[serde(skip_serializing_if = "is_false")]
+    pub underline: bool,
+}
+
+/// Style carried from one line to the next, as terminals do.
+#[derive(Debug, Clone, Default)]
+pub struct Style {
+    fg: Option<String>,
+    bg: Option<String>,
+    bold: bool,
+    italic: bool,
+    underline: bool,
+}
+
+// endregion
+
+// region: Parsing
+
+/// Split a line into styled spans. SGR sequences update `style`, other
+/// escape sequences (cursor moves, titles) are dropped.
+pub fn parse(line: &str, style: &mut Style) -> Vec<Span> {
+    let mut spans = Vec::new();
+    let mut text = String::new();
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            text.push(c);
+            continue;
+        }
+        match chars.next() {
+            // CSI: parameters until a final byte in '@'..='~'.
+            Some('[') => {
+                let mut params = String::new();
+                let mut last = None;
+                for c in chars.by_ref() {
+                    if ('@'..='~').contains(&c) {
+                        last = Some(c);
+                        break;
+                    }
+                    params.push(c);
+                }
+                if last == Some('m') {
+                    push(&mut spans, &mut text, style);
+                    apply_sgr(&params, style);
+                }
+            }
+            // OSC: until BEL or ST (ESC \).
+            Some(']') => {
+                while let Some(c) = chars.next() {
+                    if c == '\x07' || (c == '\x1b' && chars.next_if_eq(&'\\').is_some()) {
+                        break;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    push(&mut spans, &mut text, style);
+    spans
+}
+
+/// Apply `ESC[...m` parameters to a style.
+fn apply_sgr(params: &str, style: &mut Style) {
+    let codes: Vec<u16> = if params.is_empty() {
+        vec![0]
+    } else {
+        params
+            .split([';', ':'])
+            .map(|p| p.parse().unwrap_or(0))
+            .collect()
+    };
+    let mut codes = codes.into_iter();
+    while let Some(code) = codes.next() {
+        match code {
+            0 => *style = Style::default(),
+            1 => style.bold = true,
+            3 => style.italic = true,
+            4 => style.underline = true,
+            22 => style.bold = false,
+            23 => style.italic = false,
+            24 => style.underline = false,
+            30..=37 => style.fg = Some(PALETTE[(code - 30) as usize].to_string()),
+            90..=97 => style.fg = Some(PALETTE[(code - 90 + 8) as usize].to_string()),
+            40..=47 => style.bg = Some(PALETTE[(code - 40) as usize].to_string()),
+            100..=107 => style.bg = Some(PALETTE[(code - 100 + 8) as usize].to_string()),
+            39 => style.fg = None,
+            49 => style.bg = None,
+            38 | 48 => {
+                let color = match codes.next() {
+                    Some(5) => codes.next().map(xterm_color),
+                    Some(2) => {
+                        let (r, g, b) = (codes.next(), codes.next(), codes.next());
+                        Some(format!(
+                            "#{:02x}{:02x}{:02x}",
+                            r.unwrap_or(0).min(255),
+                            g.unwrap_or(0).min(255),
+                            b.unwrap_or(0).min(255)
+                        ))
+                    }
+                    _ => None,
+                };
+                if code == 38 {
+                    style.fg = color;
+                } else {
+                    style.bg = color;
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+// endregion
+
+// region: Utils
+
+/// Close the current span if it has text.
+fn push(spans: &mut Vec<Span>, text: &mut String, style: &Style) {
+    if text.is_empty() {
+        return;
+    }
+    spans.push(Span {
+        text: std::mem::take(text),
+        fg: style.fg.clone(),
+        bg: style.bg.clone(),
+        bold: style.bold,
+        italic: style.italic,
+        underline: style.underline,
+    });
+}
+
+/// Color of the xterm 256 color palette.
+fn xterm_color(index: u16) -> String {
+    match index {
+        0..=15 => PALETTE[index as usize].to_string(),
+        16..=231 => {
+            let level = |v: u16| if v == 0 { 0 } else { v * 40 + 55 };
+            let i = index - 16;
+            format!(
+                "#{:02x}{:02x}{:02x}",
+                level(i / 36),
+                level(i / 6 % 6),
+                level(i % 6)
+            )
+        }
+        _ => {
+            let gray = (index.min(255) - 232) * 10 + 8;
+            format!("#{gray:02x}{gray:02x}{gray:02x}")
+        }
+    }
+}
+
+fn is_false(value: &bool) -> bool {
+    !value
+}
+
+// endregion
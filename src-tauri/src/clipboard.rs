@@ -0,0 +1,1015 @@
+use crate::privacy::{self, Verdict};
+use crate::settings::{self, ClipboardSettings};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::io::Write;
+#[cfg(target_os = "linux")]
+use std::io::{BufRead, BufReader};
+use std::process::{Command, Stdio};
+#[cfg(target_os = "linux")]
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+#[cfg(target_os = "linux")]
+use std::sync::Once;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::{fs, thread};
+use tauri::image::Image;
+use tauri::{AppHandle, Emitter, Manager, Url};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+
+// region: Variables
+
+/// How often the clipboard is checked for text.
+const POLL_INTERVAL: Duration = Duration::from_millis(750);
+
+/// Without a change counter, images and files are checked every few polls
+/// while no text is available, as they are slower to read.
+const SLOW_POLLS: u32 = 4;
+
+/// Images larger than this (RGBA bytes) are not recorded.
+const IMAGE_LIMIT: usize = 64 * 1024 * 1024;
+
+/// Expired entries are removed every few polls (about a minute).
+const PRUNE_POLLS: u32 = 80;
+
+/// Characters of text returned in listings.
+const PREVIEW_LENGTH: usize = 300;
+
+/// History database, opened by `watch`.
+static DB: Mutex<Option<Connection>> = Mutex::new(None);
+
+/// Current clipboard settings, updated when the settings file changes.
+static CONFIG: Mutex<Option<ClipboardSettings>> = Mutex::new(None);
+
+/// Clipboard changes counted by the Linux listener, used while it runs,
+/// and whether the last change was marked as concealed.
+#[cfg(target_os = "linux")]
+static CHANGES: AtomicU64 = AtomicU64::new(0);
+#[cfg(target_os = "linux")]
+static CONCEALED: AtomicBool = AtomicBool::new(false);
+#[cfg(target_os = "linux")]
+static LISTENING: AtomicBool = AtomicBool::new(false);
+
+// endregion
+
+// region: Structs
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum EntryKind {
+    Text,
+    Image,
+    /// Copied files, one path per line.
+    Files,
+}
+
+/// History entry as listed by the tray window.
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ClipEntry {
+    pub id: i64,
+    pub kind: EntryKind,
+    /// Start of the text, or the copied paths.
+    pub preview: String,
+    /// Text length in characters, or image size in bytes.
+    pub size: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub width: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub height: Option<u32>,
+    /// Application that was in front when the entry was copied.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    pub created_at: u64,
+    pub used_at: u64,
+    pub pinned: bool,
+    /// Text held secrets, the entry expires after `sensitiveMinutes`.
+    pub sensitive: bool,
+}
+
+/// Clipboard contents read by the watcher.
+enum Content {
+    Text(String),
+    Image(Image<'static>),
+    Files(Vec<String>),
+}
+
+/// Clipboard change, read natively without touching the content.
+enum Change {
+    /// No change counter available, the content is compared instead.
+    Unknown,
+    Unchanged,
+    /// New change count, and whether the application that copied marked the
+    /// content as concealed (password managers).
+    Changed(u64, bool),
+}
+
+// endregion
+
+// region: Commands
+
+/// Return history entries, pinned first then most recently used. `query`
+/// matches words (or word starts) of text and file entries.
+#[tauri::command]
+pub fn list_clipboard(
+    query: Option<String>,
+    limit: Option<usize>,
+    offset: Option<usize>,
+) -> Result<Vec<ClipEntry>, String> {
+    let limit = limit.unwrap_or(100) as i64;
+    let offset = offset.unwrap_or(0) as i64;
+    with_db(|db| {
+        let columns = "e.id, e.kind, substr(e.content, 1, ?1), length(e.content), \
+            length(e.image), e.width, e.height, e.source, e.created_at, e.used_at, e.pinned, \
+            e.sensitive";
+        let order = "ORDER BY e.pinned DESC, e.used_at DESC LIMIT ?2 OFFSET ?3";
+        match query.as_deref().map(fts_query).filter(|q| !q.is_empty()) {
+            Some(query) => {
+                let sql = format!(
+                    "SELECT {columns} FROM entries e \
+                    JOIN entries_fts f ON f.rowid = e.id \
+                    WHERE entries_fts MATCH ?4 {order}"
+                );
+                let mut statement = db.prepare(&sql)?;
+                let rows = statement.query_map(
+                    params![PREVIEW_LENGTH as i64, limit, offset, query],
+                    read_entry,
+                )?;
+                rows.collect()
+            }
+            None => {
+                let sql = format!("SELECT {columns} FROM entries e {order}");
+                let mut statement = db.prepare(&sql)?;
+                let rows = statement
+                    .query_map(params![PREVIEW_LENGTH as i64, limit, offset], read_entry)?;
+                rows.collect()
+            }
+        }
+    })
+}
+
+/// Return the full text (or paths) of an entry.
+#[tauri::command]
+pub fn get_clipboard_text(id: i64) -> Result<String, String> {
+    with_db(|db| {
+        db.query_row("SELECT content FROM entries WHERE id = ?1", [id], |r| {
+            r.get(0)
+        })
+    })
+}
+
+/// Return an image entry as a base64 PNG.
+#[tauri::command]
+pub fn get_clipboard_image(id: i64) -> Result<String, String> {
+    let png: Vec<u8> = with_db(|db| {
+        db.query_row(
+            "SELECT image FROM entries WHERE id = ?1 AND image IS NOT NULL",
+            [id],
+            |r| r.get(0),
+        )
+    })?;
+    Ok(STANDARD.encode(png))
+}
+
+/// Put an entry back on the clipboard.
+#[tauri::command]
+pub fn copy_clipboard_entry(app: AppHandle, id: i64) -> Result<(), String> {
+    let (kind, content, image): (String, String, Option<Vec<u8>>) = with_db(|db| {
+        db.query_row(
+            "SELECT kind, content, image FROM entries WHERE id = ?1",
+            [id],
+            |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)),
+        )
+    })?;
+    let clipboard = app.clipboard();
+    match (kind.as_str(), image) {
+        ("image", Some(png)) => {
+            let image = decode_png(&png)?;
+            clipboard.write_image(&image).map_err(|e| e.to_string())?;
+        }
+        ("files", _) => {
+            let paths: Vec<&str> = content.lines().collect();
+            write_files(&paths).or_else(|_| {
+                clipboard
+                    .write_text(content.clone())
+                    .map_err(|e| e.to_string())
+            })?;
+        }
+        _ => clipboard.write_text(content).map_err(|e| e.to_string())?,
+    }
+    // The watcher sees the same contents and only moves the entry up.
+    with_db(|db| {
+        db.execute(
+            "UPDATE entries SET used_at = ?1 WHERE id = ?2",
+            params![now() as i64, id],
+        )
+    })?;
+    Ok(())
+}
+
+/// Pin or unpin an entry. Pinned entries are never removed by retention,
+/// sensitive ones included.
+#[tauri::command]
+pub fn pin_clipboard_entry(id: i64, pinned: bool) -> Result<(), String> {
+    with_db(|db| {
+        db.execute(
+            "UPDATE entries SET pinned = ?1 WHERE id = ?2",
+            params![pinned, id],
+        )
+    })
+    .map(|_| ())
+}
+
+/// Delete an entry.
+#[tauri::command]
+pub fn delete_clipboard_entry(id: i64) -> Result<(), String> {
+    with_db(|db| db.execute("DELETE FROM entries WHERE id = ?1", [id])).map(|_| ())
+}
+
+/// Delete all entries, pinned ones too unless `keep_pinned`.
+#[tauri::command]
+pub fn clear_clipboard_history(keep_pinned: Option<bool>) -> Result<usize, String> {
+    let keep_pinned = keep_pinned.unwrap_or(true);
+    with_db(|db| {
+        db.execute(
+            "DELETE FROM entries WHERE pinned = 0 OR ?1 = 0",
+            [keep_pinned],
+        )
+    })
+}
+
+// endregion
+
+// region: Watcher
+
+/// Open the history and record clipboard changes in the background.
+pub fn watch(app: &AppHandle) {
+    configure(&settings::load_or_default(app).clipboard);
+    match open(app) {
+        Ok(db) => *DB.lock().unwrap() = Some(db),
+        Err(e) => {
+            eprintln!("Clipboard - Failed to open history: {e}");
+            return;
+        }
+    }
+    let app = app.clone();
+    thread::spawn(move || {
+        let mut last_count: Option<u64> = None;
+        let mut last_text: Option<String> = None;
+        let mut last_hash = String::new();
+        let mut poll: u32 = 0;
+        loop {
+            thread::sleep(POLL_INTERVAL);
+            poll = poll.wrapping_add(1);
+            let config = config();
+            if poll % PRUNE_POLLS == 0 {
+                match with_db(|db| prune(db, &config)) {
+                    Ok(0) => {}
+                    Ok(_) => {
+                        app.emit("clipboard-changed", ()).ok();
+                    }
+                    Err(e) => eprintln!("Clipboard - Failed to remove expired entries: {e}"),
+                }
+            }
+            if !config.enabled {
+                continue;
+            }
+            let mut concealed = None;
+            let text = match read_change(last_count) {
+                Change::Unchanged => continue,
+                // The content is only read once the clipboard changed.
+                Change::Changed(count, marked) => {
+                    last_count = Some(count);
+                    concealed = Some(marked);
+                    app.clipboard().read_text().ok().filter(|t| !t.is_empty())
+                }
+                Change::Unknown => {
+                    let text = app.clipboard().read_text().ok().filter(|t| !t.is_empty());
+                    let changed = text != last_text;
+                    last_text = text.clone();
+                    if !changed && (text.is_some() || poll % SLOW_POLLS != 0) {
+                        continue;
+                    }
+                    text
+                }
+            };
+            // Copied files also come with their names as text.
+            let mut content = match read_files() {
+                Some(files) => Content::Files(files),
+                None => match text {
+                    Some(text) => Content::Text(text),
+                    None => match app.clipboard().read_image() {
+                        Ok(image) if image.rgba().len() <= IMAGE_LIMIT => {
+                            Content::Image(image.to_owned())
+                        }
+                        _ => continue,
+                    },
+                },
+            };
+            let hash = content_hash(&content);
+            if hash == last_hash {
+                continue;
+            }
+            last_hash = hash;
+            // Password managers mark what they copy as concealed.
+            if concealed.unwrap_or_else(privacy::is_concealed) {
+                println!("Clipboard - Skipped concealed copy.");
+                continue;
+            }
+            let source = frontmost_app();
+            if privacy::is_ignored(source.as_deref(), &config) {
+                println!(
+                    "Clipboard - Skipped copy from ignored app '{}'.",
+                    source.unwrap_or_default()
+                );
+                continue;
+            }
+            let mut sensitive = false;
+            if let Content::Text(text) = &content {
+                match privacy::scan(text, &config) {
+                    Verdict::Keep => {}
+                    Verdict::Sensitive(text) => {
+                        content = Content::Text(text);
+                        sensitive = true;
+                    }
+                    Verdict::Skip(kinds) => {
+                        println!("Clipboard - Skipped copy holding secrets ({kinds}).");
+                        continue;
+                    }
+                }
+            }
+            match record(&content, source, sensitive, &config) {
+                Ok(id) => {
+                    app.emit("clipboard-changed", id).ok();
+                }
+                Err(e) => eprintln!("Clipboard - Failed to record entry: {e}"),
+            }
+        }
+    });
+}
+
+/// Apply new clipboard settings.
+pub fn configure(config: &ClipboardSettings) {
+    privacy::configure(config);
+    *CONFIG.lock().unwrap() = Some(config.clone());
+}
+
+fn config() -> ClipboardSettings {
+    CONFIG.lock().unwrap().clone().unwrap_or_default()
+}
+
+// endregion
+
+// region: History
+
+/// Open the history database, creating its tables.
+fn open(app: &AppHandle) -> rusqlite::Result<Connection> {
+    let dir = app.path().app_data_dir().unwrap();
+    fs::create_dir_all(&dir).ok();
+    let db = Connection::open(dir.join("clipboard.db"))?;
+    db.execute_batch(
+        "PRAGMA journal_mode = WAL;
+        CREATE TABLE IF NOT EXISTS entries (
+            id INTEGER PRIMARY KEY,
+            kind TEXT NOT NULL,
+            content TEXT NOT NULL,
+            image BLOB,
+            width INTEGER,
+            height INTEGER,
+            hash TEXT NOT NULL UNIQUE,
+            source TEXT,
+            created_at INTEGER NOT NULL,
+            used_at INTEGER NOT NULL,
+            pinned INTEGER NOT NULL DEFAULT 0,
+            sensitive INTEGER NOT NULL DEFAULT 0
+        );
+        CREATE INDEX IF NOT EXISTS entries_used ON entries (pinned, used_at);
+        CREATE VIRTUAL TABLE IF NOT EXISTS entries_fts
+            USING fts5(content, content = 'entries', content_rowid = 'id');
+        CREATE TRIGGER IF NOT EXISTS entries_insert AFTER INSERT ON entries BEGIN
+            INSERT INTO entries_fts (rowid, content) VALUES (new.id, new.content);
+        END;
+        CREATE TRIGGER IF NOT EXISTS entries_delete AFTER DELETE ON entries BEGIN
+            INSERT INTO entries_fts (entries_fts, rowid, content)
+                VALUES ('delete', old.id, old.content);
+        END;",
+    )?;
+    // Histories created before secret detection.
+    let columns: Vec<String> = db
+        .prepare("SELECT name FROM pragma_table_info('entries')")?
+        .query_map([], |r| r.get(0))?
+        .collect::<rusqlite::Result<_>>()?;
+    if !columns.iter().any(|c| c == "sensitive") {
+        db.execute(
+            "ALTER TABLE entries ADD COLUMN sensitive INTEGER NOT NULL DEFAULT 0",
+            [],
+        )?;
+    }
+    Ok(db)
+}
+
+/// Add an entry, or move an identical one to the top. Returns its id.
+fn record(
+    content: &Content,
+    source: Option<String>,
+    sensitive: bool,
+    config: &ClipboardSettings,
+) -> Result<i64, String> {
+    // Redacted text is stored under the hash of what is kept.
+    let hash = content_hash(content);
+    let (kind, text, image, size) = match content {
+        Content::Text(text) => (EntryKind::Text, text.clone(), None, None),
+        Content::Files(files) => (EntryKind::Files, files.join("\n"), None, None),
+        Content::Image(image) => (
+            EntryKind::Image,
+            String::new(),
+            Some(encode_png(image)?),
+            Some((image.width(), image.height())),
+        ),
+    };
+    let time = now() as i64;
+    with_db(|db| {
+        let existing: Option<i64> = db
+            .query_row("SELECT id FROM entries WHERE hash = ?1", [&hash], |r| {
+                r.get(0)
+            })
+            .optional()?;
+        let id = match existing {
+            Some(id) => {
+                db.execute(
+                    "UPDATE entries SET used_at = ?1 WHERE id = ?2",
+                    params![time, id],
+                )?;
+                id
+            }
+            None => {
+                db.execute(
+                    "INSERT INTO entries
+                        (kind, content, image, width, height, hash, source, created_at, used_at,
+                        sensitive)
+                        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?8, ?9)",
+                    params![
+                        kind_name(kind),
+                        text,
+                        image,
+                        size.map(|s| s.0),
+                        size.map(|s| s.1),
+                        hash,
+                        source,
+                        time,
+                        sensitive
+                    ],
+                )?;
+                db.last_insert_rowid()
+            }
+        };
+        prune(db, config)?;
+        Ok(id)
+    })
+}
+
+/// Remove unpinned entries beyond the retention limits and expired
+/// sensitive ones. Returns the number of removed entries.
+fn prune(db: &Connection, config: &ClipboardSettings) -> rusqlite::Result<usize> {
+    let mut removed = 0;
+    if config.sensitive_minutes > 0 {
+        let cutoff = now().saturating_sub(config.sensitive_minutes * 60 * 1000);
+        removed += db.execute(
+            "DELETE FROM entries WHERE pinned = 0 AND sensitive = 1 AND used_at < ?1",
+            [cutoff as i64],
+        )?;
+    }
+    if config.max_days > 0 {
+        let cutoff = now().saturating_sub(config.max_days * 24 * 60 * 60 * 1000);
+        removed += db.execute(
+            "DELETE FROM entries WHERE pinned = 0 AND used_at < ?1",
+            [cutoff as i64],
+        )?;
+    }
+    removed += db.execute(
+        "DELETE FROM entries WHERE pinned = 0 AND id NOT IN (
+            SELECT id FROM entries WHERE pinned = 0 ORDER BY used_at DESC LIMIT ?1
+        )",
+        [config.max_entries as i64],
+    )?;
+    Ok(removed)
+}
+
+/// Run a query on the history database.
+fn with_db<T>(f: impl FnOnce(&Connection) -> rusqlite::Result<T>) -> Result<T, String> {
+    let db = DB.lock().unwrap();
+    let Some(db) = db.as_ref() else {
+        return Err("Clipboard history is not available.".to_string());
+    };
+    f(db).map_err(|e| e.to_string())
+}
+
+fn read_entry(row: &Row) -> rusqlite::Result<ClipEntry> {
+    let kind = match row.get::<_, String>(1)?.as_str() {
+        "image" => EntryKind::Image,
+        "files" => EntryKind::Files,
+        _ => EntryKind::Text,
+    };
+    let length: Option<i64> = match kind {
+        EntryKind::Image => row.get(4)?,
+        _ => row.get(3)?,
+    };
+    Ok(ClipEntry {
+        id: row.get(0)?,
+        kind,
+        preview: row.get(2)?,
+        size: length.unwrap_or(0) as usize,
+        width: row.get(5)?,
+        height: row.get(6)?,
+        source: row.get(7)?,
+        created_at: row.get::<_, i64>(8)? as u64,
+        used_at: row.get::<_, i64>(9)? as u64,
+        pinned: row.get(10)?,
+        sensitive: row.get(11)?,
+    })
+}
+
+// endregion
+
+// region: Platform
+
+/// Check the clipboard change counter, and read the concealed markers of
+/// the content once it moved.
+#[cfg(target_os = "macos")]
+fn read_change(last: Option<u64>) -> Change {
+    use objc2_app_kit::NSPasteboard;
+    let pasteboard = NSPasteboard::generalPasteboard();
+    let count = pasteboard.changeCount() as u64;
+    if last == Some(count) {
+        return Change::Unchanged;
+    }
+    let concealed = pasteboard.types().is_some_and(|types| {
+        types
+            .iter()
+            .any(|t| privacy::CONCEALED_TYPES.contains(&t.to_string().as_str()))
+    });
+    Change::Changed(count, concealed)
+}
+
+#[cfg(target_os = "windows")]
+fn read_change(last: Option<u64>) -> Change {
+    use windows_sys::Win32::System::DataExchange::{
+        CloseClipboard, GetClipboardData, GetClipboardSequenceNumber, IsClipboardFormatAvailable,
+        OpenClipboard, RegisterClipboardFormatW,
+    };
+    use windows_sys::Win32::System::Memory::{GlobalLock, GlobalUnlock};
+    // 0 when the clipboard cannot be accessed.
+    let count = unsafe { GetClipboardSequenceNumber() } as u64;
+    if count == 0 {
+        return Change::Unknown;
+    }
+    if last == Some(count) {
+        return Change::Unchanged;
+    }
+    let format = |name: &str| {
+        let name: Vec<u16> = name.encode_utf16().chain([0]).collect();
+        unsafe { RegisterClipboardFormatW(name.as_ptr()) }
+    };
+    let marked = privacy::CONCEALED_TYPES
+        .iter()
+        .any(|name| unsafe { IsClipboardFormatAvailable(format(name)) } != 0);
+    // Data kept out of the clipboard history holds a 0 in this format.
+    let history = format("CanIncludeInClipboardHistory");
+    let hidden = unsafe {
+        IsClipboardFormatAvailable(history) != 0 && OpenClipboard(std::ptr::null_mut()) != 0 && {
+            let data = GetClipboardData(history);
+            let value = GlobalLock(data) as *const u32;
+            let hidden = !value.is_null() && *value == 0;
+            if !value.is_null() {
+                GlobalUnlock(data);
+            }
+            CloseClipboard();
+            hidden
+        }
+    };
+    Change::Changed(count, marked || hidden)
+}
+
+/// Linux has no counter to query, changes are counted by a listener thread
+/// started on first use.
+#[cfg(target_os = "linux")]
+fn read_change(last: Option<u64>) -> Change {
+    static LISTENER: Once = Once::new();
+    LISTENER.call_once(|| {
+        thread::spawn(|| {
+            let result = if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+                listen_wayland()
+            } else {
+                listen_x11()
+            };
+            LISTENING.store(false, Ordering::SeqCst);
+            if let Err(e) = result {
+                eprintln!("Clipboard - Change listener stopped, comparing content instead: {e}");
+            }
+        });
+    });
+    if !LISTENING.load(Ordering::SeqCst) {
+        return Change::Unknown;
+    }
+    let count = CHANGES.load(Ordering::SeqCst);
+    if last == Some(count) {
+        return Change::Unchanged;
+    }
+    Change::Changed(count, CONCEALED.load(Ordering::SeqCst))
+}
+
+/// Record a change seen by the Linux listener.
+#[cfg(target_os = "linux")]
+fn count_change(concealed: bool) {
+    CONCEALED.store(concealed, Ordering::SeqCst);
+    CHANGES.fetch_add(1, Ordering::SeqCst);
+}
+
+/// Count clipboard owner changes reported by the XFixes extension, reading
+/// the formats of each new content.
+#[cfg(target_os = "linux")]
+fn listen_x11() -> Result<(), Box<dyn std::error::Error>> {
+    use x11rb::connection::Connection as _;
+    use x11rb::protocol::xfixes::{ConnectionExt as _, SelectionEventMask};
+    use x11rb::protocol::xproto::{AtomEnum, ConnectionExt as _, CreateWindowAux, WindowClass};
+    use x11rb::protocol::Event;
+    let (conn, screen) = x11rb::connect(None)?;
+    conn.xfixes_query_version(5, 0)?.reply()?;
+    let atom = |name: &str| -> Result<u32, Box<dyn std::error::Error>> {
+        Ok(conn.intern_atom(false, name.as_bytes())?.reply()?.atom)
+    };
+    let clipboard = atom("CLIPBOARD")?;
+    let targets = atom("TARGETS")?;
+    let property = atom("TOOLY_TARGETS")?;
+    let markers = privacy::CONCEALED_TYPES
+        .iter()
+        .map(|name| atom(name))
+        .collect::<Result<Vec<_>, _>>()?;
+    // Hidden window receiving the formats of new contents.
+    let root = conn.setup().roots[screen].root;
+    let window = conn.generate_id()?;
+    conn.create_window(
+        0,
+        window,
+        root,
+        0,
+        0,
+        1,
+        1,
+        0,
+        WindowClass::INPUT_ONLY,
+        x11rb::COPY_FROM_PARENT,
+        &CreateWindowAux::new(),
+    )?;
+    conn.xfixes_select_selection_input(window, clipboard, SelectionEventMask::SET_SELECTION_OWNER)?;
+    conn.flush()?;
+    LISTENING.store(true, Ordering::SeqCst);
+    loop {
+        match conn.wait_for_event()? {
+            Event::XfixesSelectionNotify(event) if event.owner == x11rb::NONE => {
+                count_change(false)
+            }
+            Event::XfixesSelectionNotify(event) => {
+                conn.convert_selection(
+                    window,
+                    clipboard,
+                    targets,
+                    property,
+                    event.selection_timestamp,
+                )?;
+                conn.flush()?;
+            }
+            Event::SelectionNotify(event) => {
+                let concealed = event.property != x11rb::NONE
+                    && conn
+                        .get_property(true, window, property, AtomEnum::ATOM, 0, 1024)?
+                        .reply()?
+                        .value32()
+                        .is_some_and(|mut types| types.any(|t| markers.contains(&t)));
+                count_change(concealed);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Count clipboard changes reported by `wl-paste --watch`, which needs a
+/// compositor supporting the data control protocol. It runs the command for
+/// the current content first, then on each change, with `CLIPBOARD_STATE`
+/// set to "sensitive" for concealed content.
+#[cfg(target_os = "linux")]
+fn listen_wayland() -> Result<(), Box<dyn std::error::Error>> {
+    let mut child = Command::new("wl-paste")
+        .args([
+            "--watch",
+            "sh",
+            "-c",
+            "cat > /dev/null; echo \"$CLIPBOARD_STATE\"",
+        ])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()?;
+    let stdout = child.stdout.take().ok_or("No output.")?;
+    for line in BufReader::new(stdout).lines() {
+        count_change(line? == "sensitive");
+        LISTENING.store(true, Ordering::SeqCst);
+    }
+    let status = child.wait()?;
+    Err(format!("'wl-paste --watch' exited ({status}).").into())
+}
+
+/// Read the paths of files copied in the file manager.
+#[cfg(not(target_os = "windows"))]
+fn read_files() -> Option<Vec<String>> {
+    #[cfg(target_os = "macos")]
+    let output = Command::new("osascript")
+        .args([
+            "-l",
+            "JavaScript",
+            "-e",
+            "ObjC.import('AppKit');
+            var urls = $.NSPasteboard.generalPasteboard
+                .readObjectsForClassesOptions($([$.NSURL]), $());
+            var paths = [];
+            for (var i = 0; i < urls.count; i++) {
+                var url = urls.objectAtIndex(i);
+                if (url.isFileURL) paths.push(url.path.js);
+            }
+            paths.join('\\n');",
+        ])
+        .output();
+
+    #[cfg(target_os = "linux")]
+    let output = if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        Command::new("wl-paste")
+            .args(["--no-newline", "--type", "text/uri-list"])
+            .output()
+    } else {
+        Command::new("xclip")
+            .args(["-selection", "clipboard", "-o", "-t", "text/uri-list"])
+            .output()
+    };
+
+    let output = output.ok().filter(|o| o.status.success())?;
+    let text = String::from_utf8_lossy(&output.stdout);
+    let files: Vec<String> = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| match Url::parse(line) {
+            Ok(url) if url.scheme() == "file" => url
+                .to_file_path()
+                .ok()
+                .map(|p| p.to_string_lossy().to_string()),
+            Ok(_) => None,
+            Err(_) => Some(line.to_string()),
+        })
+        .collect();
+    (!files.is_empty()).then_some(files)
+}
+
+/// Read the paths of files copied in Explorer (`CF_HDROP`).
+#[cfg(target_os = "windows")]
+fn read_files() -> Option<Vec<String>> {
+    use windows_sys::Win32::System::DataExchange::{
+        CloseClipboard, GetClipboardData, IsClipboardFormatAvailable, OpenClipboard,
+    };
+    use windows_sys::Win32::System::Ole::CF_HDROP;
+    use windows_sys::Win32::UI::Shell::DragQueryFileW;
+    let format = CF_HDROP as u32;
+    let files = unsafe {
+        if IsClipboardFormatAvailable(format) == 0 || OpenClipboard(std::ptr::null_mut()) == 0 {
+            return None;
+        }
+        let drop = GetClipboardData(format);
+        let mut files = Vec::new();
+        if !drop.is_null() {
+            // Index u32::MAX returns the number of files.
+            for index in 0..DragQueryFileW(drop, u32::MAX, std::ptr::null_mut(), 0) {
+                let len = DragQueryFileW(drop, index, std::ptr::null_mut(), 0);
+                let mut buffer = vec![0u16; len as usize + 1];
+                let len = DragQueryFileW(drop, index, buffer.as_mut_ptr(), buffer.len() as u32);
+                files.push(String::from_utf16_lossy(&buffer[..len as usize]));
+            }
+        }
+        CloseClipboard();
+        files
+    };
+    (!files.is_empty()).then_some(files)
+}
+
+/// Put file paths on the clipboard as copied files.
+fn write_files(paths: &[&str]) -> Result<(), String> {
+    #[cfg(target_os = "macos")]
+    let mut command = {
+        let mut command = Command::new("osascript");
+        command
+            .args([
+                "-l",
+                "JavaScript",
+                "-e",
+                "function run(paths) {
+                    ObjC.import('AppKit');
+                    var pasteboard = $.NSPasteboard.generalPasteboard;
+                    pasteboard.clearContents;
+                    pasteboard.writeObjects($(paths.map(function (p) {
+                        return $.NSURL.fileURLWithPath(p);
+                    })));
+                }",
+            ])
+            .args(paths);
+        command
+    };
+
+    #[cfg(target_os = "windows")]
+    let mut command = {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+        // Paths are passed through the environment to avoid quoting.
+        let mut command = Command::new("powershell");
+        command
+            .args([
+                "-NoProfile",
+                "-Command",
+                "Set-Clipboard -LiteralPath ($env:TOOLY_FILES -split \"`n\")",
+            ])
+            .env("TOOLY_FILES", paths.join("\n"))
+            .creation_flags(CREATE_NO_WINDOW);
+        command
+    };
+
+    // The clipboard tool keeps running to serve the selection.
+    #[cfg(target_os = "linux")]
+    let mut command = {
+        let mut command = if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+            let mut command = Command::new("wl-copy");
+            command.args(["--type", "text/uri-list"]);
+            command
+        } else {
+            let mut command = Command::new("xclip");
+            command.args(["-selection", "clipboard", "-t", "text/uri-list", "-i"]);
+            command
+        };
+        command.stdin(Stdio::piped());
+        command
+    };
+
+    let mut child = command
+        .stdout(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("Failed to copy files: {e}"))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        let uris: Vec<String> = paths
+            .iter()
+            .filter_map(|p| Url::from_file_path(p).ok())
+            .map(|u| u.to_string())
+            .collect();
+        stdin
+            .write_all(uris.join("\r\n").as_bytes())
+            .map_err(|e| format!("Failed to copy files: {e}"))?;
+    }
+    if cfg!(target_os = "linux") {
+        return Ok(());
+    }
+    match child.wait() {
+        Ok(status) if status.success() => Ok(()),
+        _ => Err("Failed to copy files.".to_string()),
+    }
+}
+
+/// Name of the application in front, the likely source of a copy.
+#[cfg(not(target_os = "windows"))]
+pub fn frontmost_app() -> Option<String> {
+    #[cfg(target_os = "macos")]
+    let output = Command::new("osascript")
+        .args([
+            "-l",
+            "JavaScript",
+            "-e",
+            "ObjC.import('AppKit'); \
+            $.NSWorkspace.sharedWorkspace.frontmostApplication.localizedName.js",
+        ])
+        .output();
+
+    // X11 only, Wayland does not expose the focused window.
+    #[cfg(target_os = "linux")]
+    let output = Command::new("xdotool")
+        .args(["getactivewindow", "getwindowpid"])
+        .output()
+        .and_then(|o| {
+            let pid = String::from_utf8_lossy(&o.stdout).trim().to_string();
+            let name = fs::read(format!("/proc/{pid}/comm"))?;
+            Ok(std::process::Output { stdout: name, ..o })
+        });
+
+    let output = output.ok().filter(|o| o.status.success())?;
+    let name = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (!name.is_empty()).then_some(name)
+}
+
+/// Process name (without `.exe`) of the window in front, the likely source
+/// of a copy.
+#[cfg(target_os = "windows")]
+pub fn frontmost_app() -> Option<String> {
+    use windows_sys::Win32::Foundation::CloseHandle;
+    use windows_sys::Win32::System::Threading::{
+        OpenProcess, QueryFullProcessImageNameW, PROCESS_NAME_WIN32,
+        PROCESS_QUERY_LIMITED_INFORMATION,
+    };
+    use windows_sys::Win32::UI::WindowsAndMessaging::{
+        GetForegroundWindow, GetWindowThreadProcessId,
+    };
+    let path = unsafe {
+        let window = GetForegroundWindow();
+        let mut pid = 0;
+        if window.is_null() || GetWindowThreadProcessId(window, &mut pid) == 0 || pid == 0 {
+            return None;
+        }
+        let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, pid);
+        if process.is_null() {
+            return None;
+        }
+        let mut buffer = [0u16; 1024];
+        let mut len = buffer.len() as u32;
+        let found =
+            QueryFullProcessImageNameW(process, PROCESS_NAME_WIN32, buffer.as_mut_ptr(), &mut len);
+        CloseHandle(process);
+        if found == 0 {
+            return None;
+        }
+        String::from_utf16_lossy(&buffer[..len as usize])
+    };
+    std::path::Path::new(&path)
+        .file_stem()
+        .map(|name| name.to_string_lossy().to_string())
+}
+
+// endregion
+
+// region: Utils
+
+/// Turn user input into an FTS5 query matching every word as a prefix.
+fn fts_query(input: &str) -> String {
+    input
+        .split_whitespace()
+        .map(|word| format!("\"{}\"*", word.replace('"', "\"\"")))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn content_hash(content: &Content) -> String {
+    let mut hasher = Sha256::new();
+    match content {
+        Content::Text(text) => hasher.update(format!("text\0{text}")),
+        Content::Files(files) => hasher.update(format!("files\0{}", files.join("\n"))),
+        Content::Image(image) => {
+            hasher.update(format!("image\0{}x{}\0", image.width(), image.height()));
+            hasher.update(image.rgba());
+        }
+    }
+    hex::encode(hasher.finalize())
+}
+
+fn kind_name(kind: EntryKind) -> &'static str {
+    match kind {
+        EntryKind::Text => "text",
+        EntryKind::Image => "image",
+        EntryKind::Files => "files",
+    }
+}
+
+fn encode_png(image: &Image) -> Result<Vec<u8>, String> {
+    let mut png = Vec::new();
+    let mut encoder = png::Encoder::new(&mut png, image.width(), image.height());
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(image.rgba()))
+        .map_err(|e| format!("Failed to encode image: {e}"))?;
+    Ok(png)
+}
+
+fn decode_png(png: &[u8]) -> Result<Image<'static>, String> {
+    let failed = |e: png::DecodingError| format!("Failed to decode image: {e}");
+    let mut decoder = png::Decoder::new(png);
+    decoder.set_transformations(png::Transformations::EXPAND);
+    let mut reader = decoder.read_info().map_err(failed)?;
+    let mut rgba = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut rgba).map_err(failed)?;
+    rgba.truncate(info.buffer_size());
+    Ok(Image::new_owned(rgba, info.width, info.height))
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+// endregion
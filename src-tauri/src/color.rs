@@ -0,0 +1,472 @@
+use serde::{Deserialize, Serialize};
+
+// region: Structs
+
+/// sRGB color, channels and alpha from 0 to 1.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Color {
+    pub r: f64,
+    pub g: f64,
+    pub b: f64,
+    pub a: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorFormat {
+    Hex,
+    Rgb,
+    Hsl,
+    Hsv,
+    Oklch,
+    Cmyk,
+}
+
+/// A color written in every supported format.
+#[derive(Debug, Serialize, Clone)]
+pub struct ColorValues {
+    pub hex: String,
+    pub rgb: String,
+    pub hsl: String,
+    pub hsv: String,
+    pub oklch: String,
+    pub cmyk: String,
+}
+
+// endregion
+
+// region: Conversion
+
+impl Color {
+    /// Opaque color from 8-bit channels.
+    #[cfg_attr(target_os = "linux", allow(dead_code))]
+    pub fn from_rgb8(r: u8, g: u8, b: u8) -> Self {
+        Color {
+            r: r as f64 / 255.0,
+            g: g as f64 / 255.0,
+            b: b as f64 / 255.0,
+            a: 1.0,
+        }
+    }
+
+    /// Channels as 8-bit values.
+    pub fn rgb8(&self) -> [u8; 3] {
+        [self.r, self.g, self.b].map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8)
+    }
+
+    /// Hue (degrees), saturation and lightness (0 to 1).
+    pub fn hsl(&self) -> [f64; 3] {
+        let (max, min, hue) = self.hue();
+        let lightness = (max + min) / 2.0;
+        let delta = max - min;
+        let saturation = if delta == 0.0 {
+            0.0
+        } else {
+            delta / (1.0 - (2.0 * lightness - 1.0).abs())
+        };
+        [hue, saturation, lightness]
+    }
+
+    /// Hue (degrees), saturation and value (0 to 1).
+    pub fn hsv(&self) -> [f64; 3] {
+        let (max, min, hue) = self.hue();
+        let saturation = if max == 0.0 { 0.0 } else { (max - min) / max };
+        [hue, saturation, max]
+    }
+
+    /// Lightness (0 to 1), chroma and hue (degrees) in the Oklab space.
+    pub fn oklch(&self) -> [f64; 3] {
+        let [r, g, b] = [self.r, self.g, self.b].map(to_linear);
+        let l = (0.4122214708 * r + 0.5363325363 * g + 0.0514459929 * b).cbrt();
+        let m = (0.2119034982 * r + 0.6806995451 * g + 0.1073969566 * b).cbrt();
+        let s = (0.0883024619 * r + 0.2817188376 * g + 0.6299787005 * b).cbrt();
+        let lightness = 0.2104542553 * l + 0.7936177850 * m - 0.0040720468 * s;
+        let a = 1.9779984951 * l - 2.4285922050 * m + 0.4505937099 * s;
+        let b = 0.0259040371 * l + 0.7827717662 * m - 0.8086757660 * s;
+        let chroma = (a * a + b * b).sqrt();
+        // Grays have no meaningful hue.
+        let hue = if chroma < 1e-4 {
+            0.0
+        } else {
+            b.atan2(a).to_degrees().rem_euclid(360.0)
+        };
+        [lightness, chroma, hue]
+    }
+
+    /// Cyan, magenta, yellow and key (0 to 1).
+    pub fn cmyk(&self) -> [f64; 4] {
+        let max = self.r.max(self.g).max(self.b);
+        if max == 0.0 {
+            return [0.0, 0.0, 0.0, 1.0];
+        }
+        [
+            (max - self.r) / max,
+            (max - self.g) / max,
+            (max - self.b) / max,
+            1.0 - max,
+        ]
+    }
+
+    pub fn from_hsl(hue: f64, saturation: f64, lightness: f64) -> Self {
+        let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+        from_hue(hue, chroma, lightness - chroma / 2.0)
+    }
+
+    pub fn from_hsv(hue: f64, saturation: f64, value: f64) -> Self {
+        let chroma = value * saturation;
+        from_hue(hue, chroma, value - chroma)
+    }
+
+    /// Colors outside of sRGB are clipped.
+    pub fn from_oklch(lightness: f64, chroma: f64, hue: f64) -> Self {
+        let (a, b) = (
+            chroma * hue.to_radians().cos(),
+            chroma * hue.to_radians().sin(),
+        );
+        let l = (lightness + 0.3963377774 * a + 0.2158037573 * b).powi(3);
+        let m = (lightness - 0.1055613458 * a - 0.0638541728 * b).powi(3);
+        let s = (lightness - 0.0894841775 * a - 1.2914855480 * b).powi(3);
+        let [r, g, b] = [
+            4.0767416621 * l - 3.3077115913 * m + 0.2309699292 * s,
+            -1.2684380046 * l + 2.6097574011 * m - 0.3413193965 * s,
+            -0.0041960863 * l - 0.7034186147 * m + 1.7076147010 * s,
+        ]
+        .map(|c| from_linear(c).clamp(0.0, 1.0));
+        Color { r, g, b, a: 1.0 }
+    }
+
+    pub fn from_cmyk(c: f64, m: f64, y: f64, k: f64) -> Self {
+        Color {
+            r: (1.0 - c) * (1.0 - k),
+            g: (1.0 - m) * (1.0 - k),
+            b: (1.0 - y) * (1.0 - k),
+            a: 1.0,
+        }
+    }
+
+    /// Largest and smallest channel, and the hue in degrees.
+    fn hue(&self) -> (f64, f64, f64) {
+        let (r, g, b) = (self.r, self.g, self.b);
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let delta = max - min;
+        let hue = if delta == 0.0 {
+            0.0
+        } else if max == r {
+            60.0 * ((g - b) / delta).rem_euclid(6.0)
+        } else if max == g {
+            60.0 * ((b - r) / delta + 2.0)
+        } else {
+            60.0 * ((r - g) / delta + 4.0)
+        };
+        (max, min, hue)
+    }
+}
+
+// endregion
+
+// region: Formats
+
+/// Read a color written as hex (`#rgb`, `#rrggbb` and forms with alpha,
+/// `#` optional) or as `rgb()`, `rgba()`, `hsl()`, `hsla()`, `hsv()`,
+/// `oklch()` or `cmyk()`, with commas or spaces and an optional `/ alpha`.
+pub fn parse(text: &str) -> Result<Color, String> {
+    let text = text.trim();
+    let invalid = || format!("Invalid color '{text}'.");
+    let Some((name, arguments)) = text
+        .strip_suffix(')')
+        .and_then(|t| t.split_once('('))
+        .map(|(name, arguments)| (name.trim().to_lowercase(), arguments))
+    else {
+        return parse_hex(text).ok_or_else(invalid);
+    };
+    // Values are numbers, percentages (0 to 1) or angles in degrees.
+    let values: Vec<(f64, bool)> = arguments
+        .split([',', ' ', '/'])
+        .filter(|v| !v.is_empty())
+        .map(|v| {
+            let v = v.trim_end_matches("deg");
+            match v.strip_suffix('%') {
+                Some(v) => v.parse::<f64>().map(|v| (v / 100.0, true)),
+                None => v.parse::<f64>().map(|v| (v, false)),
+            }
+        })
+        .collect::<Result<_, _>>()
+        .map_err(|_| invalid())?;
+    let count = if name == "cmyk" { 4 } else { 3 };
+    if values.len() != count && values.len() != count + 1 {
+        return Err(invalid());
+    }
+    let value = |index: usize| values[index].0;
+    // Channels without `%` are 0-255 for rgb and 0-100 elsewhere.
+    let unit = |index: usize, scale: f64| match values[index] {
+        (v, true) => v,
+        (v, false) => v / scale,
+    };
+    let mut color = match name.as_str() {
+        "rgb" | "rgba" => Color {
+            r: unit(0, 255.0),
+            g: unit(1, 255.0),
+            b: unit(2, 255.0),
+            a: 1.0,
+        },
+        "hsl" | "hsla" => Color::from_hsl(value(0), unit(1, 100.0), unit(2, 100.0)),
+        "hsv" | "hsb" => Color::from_hsv(value(0), unit(1, 100.0), unit(2, 100.0)),
+        // Lightness is a percentage or a number from 0 to 1, a chroma of
+        // 100% is 0.4.
+        "oklch" => {
+            let chroma = match values[1] {
+                (v, true) => v * 0.4,
+                (v, false) => v,
+            };
+            Color::from_oklch(value(0), chroma, value(2))
+        }
+        "cmyk" => Color::from_cmyk(
+            unit(0, 100.0),
+            unit(1, 100.0),
+            unit(2, 100.0),
+            unit(3, 100.0),
+        ),
+        _ => return Err(invalid()),
+    };
+    if let Some(&(alpha, _)) = values.get(count) {
+        color.a = alpha;
+    }
+    color.r = color.r.clamp(0.0, 1.0);
+    color.g = color.g.clamp(0.0, 1.0);
+    color.b = color.b.clamp(0.0, 1.0);
+    color.a = color.a.clamp(0.0, 1.0);
+    Ok(color)
+}
+
+/// Write a color in a format, the way CSS does where it can.
+pub fn format(color: &Color, format: ColorFormat) -> String {
+    let alpha = (color.a < 1.0).then(|| round(color.a, 2));
+    let percent = |v: f64| format!("{}%", round(v * 100.0, 1));
+    match format {
+        ColorFormat::Hex => {
+            let [r, g, b] = color.rgb8();
+            match alpha {
+                Some(_) => format!(
+                    "#{r:02x}{g:02x}{b:02x}{:02x}",
+                    (color.a * 255.0).round() as u8
+                ),
+                None => format!("#{r:02x}{g:02x}{b:02x}"),
+            }
+        }
+        ColorFormat::Rgb => {
+            let [r, g, b] = color.rgb8();
+            match alpha {
+                Some(a) => format!("rgba({r}, {g}, {b}, {a})"),
+                None => format!("rgb({r}, {g}, {b})"),
+            }
+        }
+        ColorFormat::Hsl => {
+            let [h, s, l] = color.hsl();
+            let values = format!("{}, {}, {}", round(h, 1), percent(s), percent(l));
+            match alpha {
+                Some(a) => format!("hsla({values}, {a})"),
+                None => format!("hsl({values})"),
+            }
+        }
+        ColorFormat::Hsv => {
+            let [h, s, v] = color.hsv();
+            format!("hsv({}, {}, {})", round(h, 1), percent(s), percent(v))
+        }
+        ColorFormat::Oklch => {
+            let [l, c, h] = color.oklch();
+            let values = format!("{} {} {}", percent(l), round(c, 4), round(h, 2));
+            match alpha {
+                Some(a) => format!("oklch({values} / {a})"),
+                None => format!("oklch({values})"),
+            }
+        }
+        ColorFormat::Cmyk => {
+            let [c, m, y, k] = color.cmyk();
+            format!(
+                "cmyk({}, {}, {}, {})",
+                percent(c),
+                percent(m),
+                percent(y),
+                percent(k)
+            )
+        }
+    }
+}
+
+/// Write a color in every format.
+pub fn values(color: &Color) -> ColorValues {
+    ColorValues {
+        hex: format(color, ColorFormat::Hex),
+        rgb: format(color, ColorFormat::Rgb),
+        hsl: format(color, ColorFormat::Hsl),
+        hsv: format(color, ColorFormat::Hsv),
+        oklch: format(color, ColorFormat::Oklch),
+        cmyk: format(color, ColorFormat::Cmyk),
+    }
+}
+
+// endregion
+
+// region: Utils
+
+fn parse_hex(text: &str) -> Option<Color> {
+    let hex = text.strip_prefix('#').unwrap_or(text);
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    // Short forms repeat each digit (`#f80` is `#ff8800`).
+    let digits: String = match hex.len() {
+        3 | 4 => hex.chars().flat_map(|c| [c, c]).collect(),
+        6 | 8 => hex.to_string(),
+        _ => return None,
+    };
+    let channel = |index: usize| {
+        u8::from_str_radix(&digits[index * 2..index * 2 + 2], 16).unwrap() as f64 / 255.0
+    };
+    Some(Color {
+        r: channel(0),
+        g: channel(1),
+        b: channel(2),
+        a: if digits.len() == 8 { channel(3) } else { 1.0 },
+    })
+}
+
+/// Color from a hue, chroma and the amount added to every channel.
+fn from_hue(hue: f64, chroma: f64, offset: f64) -> Color {
+    let sector = hue.rem_euclid(360.0) / 60.0;
+    let x = chroma * (1.0 - (sector.rem_euclid(2.0) - 1.0).abs());
+    let (r, g, b) = match sector as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    Color {
+        r: (r + offset).clamp(0.0, 1.0),
+        g: (g + offset).clamp(0.0, 1.0),
+        b: (b + offset).clamp(0.0, 1.0),
+        a: 1.0,
+    }
+}
+
+/// sRGB transfer function, to linear light.
+fn to_linear(c: f64) -> f64 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn from_linear(c: f64) -> f64 {
+    if c <= 0.0031308 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// Round to a number of decimals, dropping trailing zeros when printed.
+fn round(value: f64, decimals: i32) -> f64 {
+    let factor = 10f64.powi(decimals);
+    let rounded = (value * factor).round() / factor;
+    // Avoid printing "-0".
+    if rounded == 0.0 {
+        0.0
+    } else {
+        rounded
+    }
+}
+
+// endregion
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL_FORMATS: [ColorFormat; 6] = [
+        ColorFormat::Hex,
+        ColorFormat::Rgb,
+        ColorFormat::Hsl,
+        ColorFormat::Hsv,
+        ColorFormat::Oklch,
+        ColorFormat::Cmyk,
+    ];
+
+    fn assert_close(actual: &[f64], expected: &[f64]) {
+        for (a, e) in actual.iter().zip(expected) {
+            assert!((a - e).abs() < 1e-3, "{actual:?} != {expected:?}");
+        }
+    }
+
+    #[test]
+    fn writes_every_format() {
+        let values = values(&parse("#ff8800").unwrap());
+        assert_eq!(values.hex, "#ff8800");
+        assert_eq!(values.rgb, "rgb(255, 136, 0)");
+        assert_eq!(values.hsl, "hsl(32, 100%, 50%)");
+        assert_eq!(values.hsv, "hsv(32, 100%, 100%)");
+        assert_eq!(values.oklch, "oklch(74.4% 0.1812 56.46)");
+        assert_eq!(values.cmyk, "cmyk(0%, 46.7%, 100%, 0%)");
+    }
+
+    #[test]
+    fn round_trips_every_format() {
+        let color = parse("#ff8800").unwrap();
+        for format in ALL_FORMATS {
+            let text = super::format(&color, format);
+            let parsed = parse(&text).unwrap();
+            assert_eq!(parsed.rgb8(), [255, 136, 0], "{text}");
+        }
+    }
+
+    #[test]
+    fn matches_oklch_references() {
+        assert_close(&parse("#ffffff").unwrap().oklch()[..2], &[1.0, 0.0]);
+        assert_close(&parse("#000000").unwrap().oklch(), &[0.0, 0.0, 0.0]);
+        assert_close(
+            &parse("#ff0000").unwrap().oklch(),
+            &[0.62796, 0.25768, 29.2339],
+        );
+        assert_eq!(
+            format(&parse("#ffffff").unwrap(), ColorFormat::Oklch),
+            "oklch(100% 0 0)"
+        );
+        assert_eq!(
+            Color::from_oklch(0.62796, 0.25768, 29.2339).rgb8(),
+            [255, 0, 0]
+        );
+    }
+
+    #[test]
+    fn converts_black_to_cmyk() {
+        let black = parse("#000000").unwrap();
+        assert_eq!(black.cmyk(), [0.0, 0.0, 0.0, 1.0]);
+        assert_eq!(format(&black, ColorFormat::Cmyk), "cmyk(0%, 0%, 0%, 100%)");
+        assert_eq!(parse("cmyk(0%, 0%, 0%, 100%)").unwrap().rgb8(), [0, 0, 0]);
+    }
+
+    #[test]
+    fn keeps_alpha() {
+        let color = parse("#ff880080").unwrap();
+        assert!((color.a - 128.0 / 255.0).abs() < 1e-9);
+        assert_eq!(format(&color, ColorFormat::Hex), "#ff880080");
+        assert_eq!(format(&color, ColorFormat::Rgb), "rgba(255, 136, 0, 0.5)");
+        assert_eq!(format(&color, ColorFormat::Hsl), "hsla(32, 100%, 50%, 0.5)");
+        assert_eq!(
+            format(&color, ColorFormat::Oklch),
+            "oklch(74.4% 0.1812 56.46 / 0.5)"
+        );
+        for text in [
+            "rgba(255, 136, 0, 0.5)",
+            "hsl(32 100% 50% / 0.5)",
+            "oklch(74.4% 0.1812 56.46 / 0.5)",
+        ] {
+            assert!((parse(text).unwrap().a - 0.5).abs() < 0.01, "{text}");
+        }
+        assert_eq!(parse("rgba(0, 0, 0, 2)").unwrap().a, 1.0);
+    }
+}
@@ -0,0 +1,229 @@
+use crate::journal::{self, OperationKind};
+use crate::template::{self, Quote};
+use crate::templates;
+use crate::utils::Payload;
+use std::path::{Component, Path, PathBuf};
+use std::{fs, io};
+
+// region: Create
+
+/// Create what a `create` action describes in the target folder and return
+/// the path to reveal:
+///
+/// - `name|content`: a text file (`New File.txt` when the name is empty).
+/// - `folder:<spec>`: folders, with braces for several at once
+///   (`src/{components,hooks}`). Existing folders are kept.
+/// - `copy:<source>|<name>`: a copy of any file or folder, binary included.
+/// - `template:<name>|<new name>`: a copy of a library template.
+///
+/// Placeholders are replaced in every part. New names get a `(n)` suffix
+/// when taken.
+pub fn create(info: &Payload) -> Result<PathBuf, String> {
+    let context = info.context(0);
+    let render = |value: &str| template::render(value, &context, Quote::None);
+    let target = Path::new(&info.target);
+    if let Some((name, new_name)) = templates::parse_action(&info.action) {
+        let app = crate::get_app_handle().clone();
+        return templates::instantiate(&app, name, &render(new_name)?, target, &context);
+    }
+    if let Some(spec) = info.action.strip_prefix("folder:") {
+        return create_folders(target, &render(spec)?);
+    }
+    if let Some(spec) = info.action.strip_prefix("copy:") {
+        let (source, name) = spec.split_once('|').unwrap_or((spec, ""));
+        return create_copy(target, Path::new(&render(source)?), &render(name)?);
+    }
+    // Name and content are separated by the first '|'.
+    let (name, content) = info.action.split_once('|').unwrap_or((&info.action, ""));
+    let name = match render(name)? {
+        name if name.is_empty() => "New File.txt".to_string(),
+        name => name,
+    };
+    let path = unique_path(target, &name)?;
+    fs::write(&path, render(content)?)
+        .map_err(|e| format!("Failed to create file in '{}': {e}", target.display()))?;
+    journal::record_create(&path);
+    Ok(path)
+}
+
+/// Create the folders of a brace spec, returning the first top level one.
+fn create_folders(target: &Path, spec: &str) -> Result<PathBuf, String> {
+    let mut created = Vec::new();
+    let mut first = None;
+    for relative in expand_braces(spec) {
+        let relative = Path::new(relative.trim());
+        check_inside(relative)?;
+        let mut path = target.to_path_buf();
+        for component in relative.components() {
+            path.push(component);
+            first.get_or_insert_with(|| path.clone());
+            if path.is_dir() {
+                continue;
+            }
+            let result = fs::create_dir(&path);
+            if let Err(e) = result {
+                record_all(created);
+                return Err(format!("Failed to create '{}': {e}", path.display()));
+            }
+            created.push(path.clone());
+        }
+    }
+    record_all(created);
+    first.ok_or("Folder spec cannot be empty.".to_string())
+}
+
+/// Copy a file or folder into the target under `name` (the source name when
+/// empty).
+fn create_copy(target: &Path, source: &Path, name: &str) -> Result<PathBuf, String> {
+    if !source.exists() {
+        return Err(format!("Source '{}' does not exist.", source.display()));
+    }
+    let name = match name {
+        "" => source
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .ok_or(format!("Invalid source '{}'.", source.display()))?,
+        name => name.to_string(),
+    };
+    let path = unique_path(target, &name)?;
+    let mut created = Vec::new();
+    let result = copy_tree(source, &path, &mut created);
+    record_all(created);
+    result
+        .map(|_| path)
+        .map_err(|e| format!("Failed to copy '{}': {e}", source.display()))
+}
+
+// endregion
+
+// region: Utils
+
+/// Copy a file or folder tree without changes, listing the created paths.
+pub fn copy_tree(source: &Path, destination: &Path, created: &mut Vec<PathBuf>) -> io::Result<()> {
+    if !source.is_dir() {
+        fs::copy(source, destination)?;
+        created.push(destination.to_path_buf());
+        return Ok(());
+    }
+    fs::create_dir(destination)?;
+    created.push(destination.to_path_buf());
+    for entry in fs::read_dir(source)? {
+        let entry = entry?;
+        copy_tree(&entry.path(), &destination.join(entry.file_name()), created)?;
+    }
+    Ok(())
+}
+
+/// Record created paths as one operation. Undo removes files before the
+/// folders holding them.
+pub fn record_all(mut created: Vec<PathBuf>) {
+    created.reverse();
+    let pairs: Vec<(PathBuf, PathBuf)> = created
+        .into_iter()
+        .map(|path| (PathBuf::new(), path))
+        .collect();
+    journal::record(OperationKind::Create, &pairs);
+}
+
+/// Return `target/file_name`, with a `(n)` suffix before the extension
+/// while that path is taken. Names leaving the target are rejected.
+pub fn unique_path(target_path: &Path, file_name: &str) -> Result<PathBuf, String> {
+    check_inside(Path::new(file_name))?;
+    let mut new_file_path = target_path.join(file_name);
+    let mut counter = 1;
+
+    while new_file_path.exists() {
+        let file_stem = target_path
+            .join(Path::new(file_name).file_stem().unwrap_or_default())
+            .to_string_lossy()
+            .to_string();
+        let extension = Path::new(file_name)
+            .extension()
+            .map(|ext| format!(".{}", ext.to_string_lossy()))
+            .unwrap_or_default();
+        new_file_path = target_path.join(format!("{} ({}){}", file_stem, counter, extension));
+        counter += 1;
+    }
+    Ok(new_file_path)
+}
+
+/// Check that a rendered name or folder is a relative path inside the target.
+fn check_inside(relative: &Path) -> Result<(), String> {
+    let valid = relative
+        .components()
+        .all(|c| matches!(c, Component::Normal(_) | Component::CurDir));
+    if !valid || relative.as_os_str().is_empty() {
+        return Err(format!(
+            "Invalid name '{}', expected a path inside the target.",
+            relative.display()
+        ));
+    }
+    Ok(())
+}
+
+/// Check that a rendered entry name is a single file or folder name.
+pub fn check_file_name(name: &str) -> Result<(), String> {
+    let mut components = Path::new(name).components();
+    match (components.next(), components.next()) {
+        (Some(Component::Normal(_)), None) => Ok(()),
+        _ => Err(format!(
+            "Invalid name '{name}', expected a single file or folder name."
+        )),
+    }
+}
+
+/// Expand shell style braces: `a/{b,c/{d,e}}` gives `a/b`, `a/c/d` and
+/// `a/c/e`. Braces without a comma are kept.
+fn expand_braces(spec: &str) -> Vec<String> {
+    let mut depth = 0;
+    let mut start = 0;
+    for (index, c) in spec.char_indices() {
+        match c {
+            '{' => {
+                if depth == 0 {
+                    start = index;
+                }
+                depth += 1;
+            }
+            '}' if depth > 0 => {
+                depth -= 1;
+                if depth > 0 {
+                    continue;
+                }
+                let parts = split_top_level(&spec[start + 1..index]);
+                if parts.len() < 2 {
+                    continue;
+                }
+                let (prefix, suffix) = (&spec[..start], &spec[index + 1..]);
+                return parts
+                    .iter()
+                    .flat_map(|part| expand_braces(&format!("{prefix}{part}{suffix}")))
+                    .collect();
+            }
+            _ => {}
+        }
+    }
+    vec![spec.to_string()]
+}
+
+/// Split on commas outside of nested braces.
+fn split_top_level(value: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (index, c) in value.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            ',' if depth == 0 => {
+                parts.push(&value[start..index]);
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&value[start..]);
+    parts
+}
+
+// endregion
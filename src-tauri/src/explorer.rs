@@ -0,0 +1,410 @@
+use crate::settings::{self, MenuItem, MenuSettings};
+use std::collections::BTreeSet;
+use std::path::Path;
+use tauri::AppHandle;
+
+// region: Variables
+
+/// Root of per-user file associations.
+const CLASSES_ROOT: &str = "HKEY_CURRENT_USER\\Software\\Classes";
+
+/// Header of version 5 registry files.
+const REG_HEADER: &str = "Windows Registry Editor Version 5.00\r\n";
+
+/// Name of the cascading menu key created under each `shell` key.
+const MENU_KEY: &str = "Tooly";
+
+/// Locations for selected files, selected folders and folder backgrounds.
+const FILES: &str = "*";
+const FOLDERS: &str = "Directory";
+const BACKGROUND: &str = "Directory\\Background";
+
+// endregion
+
+// region: Commands
+
+/// Add the Tooly cascading menu to Explorer (Windows only).
+#[tauri::command]
+pub fn install_explorer_menu(app: AppHandle) -> Result<(), String> {
+    install(&app)
+}
+
+/// Remove the Tooly cascading menu from Explorer (Windows only).
+#[tauri::command]
+pub fn uninstall_explorer_menu(app: AppHandle) -> Result<(), String> {
+    uninstall(&app)
+}
+
+// endregion
+
+// region: Registry
+
+/// Regenerate Explorer entries from the current settings.
+pub fn install(app: &AppHandle) -> Result<(), String> {
+    let settings = settings::load(app).map_err(|e| e.to_string())?;
+    let exe = std::env::current_exe().map_err(|e| e.to_string())?;
+    // Drop menus for extensions that are no longer used before adding new ones.
+    let mut content = uninstall_reg(&installed_extensions(app));
+    content.push_str(install_reg(&settings, &exe).trim_start_matches(REG_HEADER));
+    import_reg(app, &content)?;
+    save_installed_extensions(app, &extensions(&settings));
+    println!("Explorer - Updated context menu entries.");
+    Ok(())
+}
+
+/// Remove every Explorer entry created by Tooly.
+pub fn uninstall(app: &AppHandle) -> Result<(), String> {
+    let mut known = installed_extensions(app);
+    known.extend(extensions(&settings::load_or_default(app)));
+    import_reg(app, &uninstall_reg(&known))?;
+    save_installed_extensions(app, &BTreeSet::new());
+    println!("Explorer - Removed context menu entries.");
+    Ok(())
+}
+
+/// Build the `.reg` content adding Tooly menus for the given settings.
+///
+/// Items targeting "any" appear for files, folders and folder backgrounds,
+/// "file" and "folder" items for their kind only, and extension lists under
+/// `SystemFileAssociations\.ext`. Explorer runs `exe --run <id> <path>` once
+/// per selected file.
+pub fn install_reg(settings: &MenuSettings, exe: &Path) -> String {
+    let mut reg = String::from(REG_HEADER);
+    let items = enabled_items(settings);
+    let mut locations: Vec<(String, bool)> = vec![
+        (FILES.to_string(), false),
+        (FOLDERS.to_string(), false),
+        (BACKGROUND.to_string(), true),
+    ];
+    for extension in extensions(settings) {
+        locations.push((format!("SystemFileAssociations\\.{extension}"), false));
+    }
+    for (location, background) in locations {
+        let entries: Vec<&(&String, &MenuItem)> = items
+            .iter()
+            .filter(|(_, item)| applies_to(item, &location))
+            .collect();
+        if entries.is_empty() {
+            continue;
+        }
+        let root = format!("{CLASSES_ROOT}\\{location}\\shell\\{MENU_KEY}");
+        reg.push_str(&submenu_key(
+            &root,
+            MENU_KEY,
+            &format!("{},0", exe.display()),
+        ));
+        // Groups become nested cascading menus, placed where their first item is.
+        let mut groups: Vec<(&str, String)> = Vec::new();
+        for (index, (id, item)) in entries.iter().enumerate() {
+            let parent = if item.group.is_empty() {
+                root.clone()
+            } else if let Some((_, key)) = groups.iter().find(|(g, _)| *g == item.group) {
+                key.clone()
+            } else {
+                let group_key = format!("{root}\\shell\\{}", key_name(index, &item.group));
+                let group = settings.groups.get(&item.group);
+                let icon = group
+                    .map(|g| icon_value(&g.icon_type, &g.icon))
+                    .unwrap_or_default();
+                reg.push_str(&submenu_key(&group_key, &item.group, &icon));
+                groups.push((&item.group, group_key.clone()));
+                group_key
+            };
+            let key = format!("{parent}\\shell\\{}", key_name(index, id));
+            let argument = if background { "%V" } else { "%1" };
+            let command = format!(
+                "{} --run {} {}",
+                quote_arg(&exe.to_string_lossy()),
+                quote_arg(id),
+                quote_arg(argument)
+            );
+            reg.push_str(&format!(
+                "\r\n[{key}]\r\n\"MUIVerb\"={}\r\n",
+                reg_string(id)
+            ));
+            let icon = icon_value(&item.icon_type, &item.icon);
+            if !icon.is_empty() {
+                reg.push_str(&format!("\"Icon\"={}\r\n", reg_string(&icon)));
+            }
+            reg.push_str(&format!(
+                "\r\n[{key}\\command]\r\n@={}\r\n",
+                reg_string(&command)
+            ));
+        }
+    }
+    reg
+}
+
+/// Build the `.reg` content removing Tooly menus, including the ones placed
+/// under the given file extensions.
+pub fn uninstall_reg(extensions: &BTreeSet<String>) -> String {
+    let mut reg = String::from(REG_HEADER);
+    let mut locations = vec![
+        FILES.to_string(),
+        FOLDERS.to_string(),
+        BACKGROUND.to_string(),
+    ];
+    locations.extend(
+        extensions
+            .iter()
+            .map(|e| format!("SystemFileAssociations\\.{e}")),
+    );
+    for location in locations {
+        reg.push_str(&format!(
+            "\r\n[-{CLASSES_ROOT}\\{location}\\shell\\{MENU_KEY}]\r\n"
+        ));
+    }
+    reg
+}
+
+// endregion
+
+// region: Utils
+
+/// Return enabled items in menu order.
+fn enabled_items(settings: &MenuSettings) -> Vec<(&String, &MenuItem)> {
+    settings
+        .order
+        .iter()
+        .filter_map(|id| settings.items.get_key_value(id))
+        .filter(|(_, item)| item.enabled)
+        .collect()
+}
+
+/// Return the lowercase extensions targeted by enabled items.
+fn extensions(settings: &MenuSettings) -> BTreeSet<String> {
+    enabled_items(settings)
+        .iter()
+        .filter(|(_, item)| !matches!(item.target_type.as_str(), "any" | "file" | "folder"))
+        .flat_map(|(_, item)| item.target_type.split(','))
+        .map(|e| e.trim().to_lowercase())
+        .filter(|e| !e.is_empty())
+        .collect()
+}
+
+/// Check whether an item belongs under a registry location.
+fn applies_to(item: &MenuItem, location: &str) -> bool {
+    match item.target_type.as_str() {
+        "any" => matches!(location, FILES | FOLDERS | BACKGROUND),
+        "file" => location == FILES,
+        "folder" => location == FOLDERS,
+        extensions => extensions.split(',').any(|e| {
+            location.eq_ignore_ascii_case(&format!("SystemFileAssociations\\.{}", e.trim()))
+        }),
+    }
+}
+
+/// Cascading menu key (a parent with its own `shell` sub key).
+fn submenu_key(key: &str, title: &str, icon: &str) -> String {
+    let mut reg = format!(
+        "\r\n[{key}]\r\n\"MUIVerb\"={}\r\n\"SubCommands\"=\"\"\r\n",
+        reg_string(title)
+    );
+    if !icon.is_empty() {
+        reg.push_str(&format!("\"Icon\"={}\r\n", reg_string(icon)));
+    }
+    reg
+}
+
+/// Explorer sorts verbs by key name, so prefix keys with their position.
+fn key_name(index: usize, id: &str) -> String {
+    let name: String = id
+        .chars()
+        .map(|c| if c == '\\' || c.is_control() { '_' } else { c })
+        .collect();
+    format!("{index:03} {name}")
+}
+
+/// Explorer can only show icons from files (`.ico`, `.exe`, `.dll`).
+fn icon_value(icon_type: &str, icon: &str) -> String {
+    match icon_type {
+        "app" | "image" if !icon.is_empty() => icon.to_string(),
+        _ => String::new(),
+    }
+}
+
+/// Quote a `.reg` string value.
+fn reg_string(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Quote a command line argument following the Windows argv rules.
+fn quote_arg(arg: &str) -> String {
+    let mut quoted = String::from("\"");
+    let mut backslashes = 0;
+    for c in arg.chars() {
+        match c {
+            '\\' => backslashes += 1,
+            '"' => {
+                quoted.push_str(&"\\".repeat(backslashes * 2 + 1));
+                quoted.push('"');
+                backslashes = 0;
+            }
+            _ => {
+                quoted.push_str(&"\\".repeat(backslashes));
+                quoted.push(c);
+                backslashes = 0;
+            }
+        }
+    }
+    quoted.push_str(&"\\".repeat(backslashes * 2));
+    quoted.push('"');
+    quoted
+}
+
+/// Return the extensions whose menus were installed last time.
+fn installed_extensions(app: &AppHandle) -> BTreeSet<String> {
+    std::fs::read_to_string(settings::settings_dir(app).join("explorer-extensions.txt"))
+        .map(|s| s.lines().map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+/// Remember installed extensions so their menus can be removed later.
+fn save_installed_extensions(app: &AppHandle, extensions: &BTreeSet<String>) {
+    let list: Vec<&str> = extensions.iter().map(String::as_str).collect();
+    let path = settings::settings_dir(app).join("explorer-extensions.txt");
+    if let Err(e) = std::fs::write(path, list.join("\n")) {
+        eprintln!("Explorer - Failed to save installed extensions: {e}");
+    }
+}
+
+/// Write `.reg` content as UTF-16 and import it with `reg import`.
+#[cfg(target_os = "windows")]
+fn import_reg(app: &AppHandle, content: &str) -> Result<(), String> {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+    let dir = settings::settings_dir(app);
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let path = dir.join("explorer.reg");
+    let mut bytes = vec![0xFF, 0xFE];
+    bytes.extend(content.encode_utf16().flat_map(u16::to_le_bytes));
+    std::fs::write(&path, bytes).map_err(|e| e.to_string())?;
+    let status = std::process::Command::new("reg")
+        .arg("import")
+        .arg(&path)
+        .creation_flags(CREATE_NO_WINDOW)
+        .status()
+        .map_err(|e| e.to_string())?;
+    std::fs::remove_file(&path).ok();
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("'reg import' failed ({status})."))
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+fn import_reg(_app: &AppHandle, _content: &str) -> Result<(), String> {
+    Err("Explorer integration is only available on Windows.".to_string())
+}
+
+// endregion
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::ScriptOptions;
+    use std::path::PathBuf;
+
+    fn item(target_type: &str) -> MenuItem {
+        MenuItem {
+            group: String::new(),
+            target_type: target_type.to_string(),
+            icon_type: String::new(),
+            icon: String::new(),
+            action_type: "script".to_string(),
+            action: "echo".to_string(),
+            key: String::new(),
+            enabled: true,
+            confirm: false,
+            options: ScriptOptions {
+                shell: "bash".to_string(),
+                ..ScriptOptions::default()
+            },
+            inputs: Vec::new(),
+        }
+    }
+
+    fn settings(items: &[(&str, MenuItem)]) -> MenuSettings {
+        let mut settings = MenuSettings::default();
+        for (id, item) in items {
+            settings.order.push(id.to_string());
+            settings.items.insert(id.to_string(), item.clone());
+        }
+        // Fixtures must be settings a user could actually save.
+        settings::validate(&settings).unwrap();
+        settings
+    }
+
+    #[test]
+    fn quotes_paths_with_spaces_and_quotes() {
+        assert_eq!(
+            quote_arg(r"C:\Program Files\Tooly"),
+            r#""C:\Program Files\Tooly""#
+        );
+        assert_eq!(quote_arg(r#"say "hi""#), r#""say \"hi\"""#);
+        assert_eq!(quote_arg(r"C:\dir\"), r#""C:\dir\\""#);
+        assert_eq!(quote_arg(r#"a\"b"#), r#""a\\\"b""#);
+        assert_eq!(reg_string(r#"C:\a "b""#), r#""C:\\a \"b\"""#);
+    }
+
+    #[test]
+    fn escapes_command_for_registry() {
+        let settings = settings(&[(r#"Say "hi""#, item("any"))]);
+        let exe = PathBuf::from(r"C:\Program Files\Tooly\tooly.exe");
+        let reg = install_reg(&settings, &exe);
+        assert!(reg.contains(
+            r#"@="\"C:\\Program Files\\Tooly\\tooly.exe\" --run \"Say \\\"hi\\\"\" \"%1\"""#
+        ));
+        assert!(reg.contains(
+            r#"@="\"C:\\Program Files\\Tooly\\tooly.exe\" --run \"Say \\\"hi\\\"\" \"%V\"""#
+        ));
+        assert!(reg.contains(r#""MUIVerb"="Say \"hi\"""#));
+    }
+
+    #[test]
+    fn places_items_by_target() {
+        let mut disabled = item("zip");
+        disabled.enabled = false;
+        let settings = settings(&[
+            ("Any", item("any")),
+            ("File", item("file")),
+            ("Folder", item("folder")),
+            ("Images", item("png,jpg")),
+            ("Zip", disabled),
+        ]);
+        let reg = install_reg(&settings, Path::new("tooly.exe"));
+        let shell = |location: &str| format!("[{CLASSES_ROOT}\\{location}\\shell\\{MENU_KEY}]");
+        for location in [FILES, FOLDERS, BACKGROUND] {
+            assert!(reg.contains(&shell(location)));
+        }
+        assert!(reg.contains(&shell("SystemFileAssociations\\.png")));
+        assert!(reg.contains(&shell("SystemFileAssociations\\.jpg")));
+        assert!(!reg.contains("SystemFileAssociations\\.zip"));
+        let images =
+            format!("{CLASSES_ROOT}\\SystemFileAssociations\\.png\\shell\\{MENU_KEY}\\shell\\");
+        assert!(reg.contains(&format!("[{images}000 Images]")));
+        assert!(!reg.contains(&format!("{images}000 Any")));
+        assert_eq!(
+            extensions(&settings),
+            BTreeSet::from(["jpg".to_string(), "png".to_string()])
+        );
+    }
+
+    #[test]
+    fn uninstall_covers_every_location() {
+        let settings = settings(&[("Any", item("any")), ("Images", item("png,jpg"))]);
+        let reg = uninstall_reg(&extensions(&settings));
+        let installed = install_reg(&settings, Path::new("tooly.exe"));
+        let roots: Vec<&str> = installed
+            .lines()
+            .filter_map(|line| line.strip_prefix('['))
+            .filter(|key| key.ends_with(&format!("\\shell\\{MENU_KEY}]")))
+            .collect();
+        assert_eq!(roots.len(), 5);
+        for root in roots {
+            assert!(reg.contains(&format!("[-{root}")));
+        }
+        assert!(reg.starts_with(REG_HEADER));
+    }
+}
@@ -0,0 +1,865 @@
+use crate::ansi::{self, Span, Style};
+use crate::settings::ExecMode;
+use crate::template::{self, Quote};
+use crate::utils::Payload;
+use crate::{terminal, windows};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::{Emitter, Manager};
+use tauri_plugin_notification::NotificationExt;
+use wait_timeout::ChildExt;
+
+// region: Variables
+
+/// Seconds before a script is stopped, unless the item sets its own limit.
+pub const SCRIPT_TIMEOUT: u64 = 120;
+
+/// Number of finished jobs kept for `list_jobs`.
+const HISTORY_LIMIT: usize = 50;
+
+/// Bytes of output kept per job (the end of the output is kept).
+const OUTPUT_LIMIT: usize = 16 * 1024;
+
+/// Number of log files kept in the `logs` folder.
+const LOG_LIMIT: usize = 50;
+
+/// Time given to background processes to release the output pipes after
+/// a script exits, before they are killed.
+const PIPE_GRACE: Duration = Duration::from_secs(2);
+
+/// Scripts running at the same time, others wait in the queue.
+const MAX_RUNNING: usize = 4;
+
+/// Recent jobs, oldest first.
+static JOBS: Mutex<VecDeque<Job>> = Mutex::new(VecDeque::new());
+
+/// Queued and running scripts.
+static STATE: Mutex<Option<State>> = Mutex::new(None);
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+// endregion
+
+// region: Structs
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Succeeded,
+    Failed,
+    TimedOut,
+    Cancelled,
+}
+
+/// A single script run.
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Job {
+    pub id: u64,
+    pub action: String,
+    pub items: Vec<String>,
+    pub status: JobStatus,
+    /// Queue, start and end times in milliseconds since the epoch.
+    pub queued_at: u64,
+    pub started_at: Option<u64>,
+    pub ended_at: Option<u64>,
+    pub exit_code: Option<i32>,
+    /// Combined stdout and stderr, truncated to the last 16 KiB.
+    pub output: String,
+    /// Full output without escape sequences, in the app data `logs` folder.
+    pub log: String,
+    /// Batch this job belongs to, for per-item execution modes.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub batch: Option<u64>,
+}
+
+/// Summary of a per-item run, emitted as `batch-finished`.
+#[derive(Debug, Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchReport {
+    pub id: u64,
+    pub action: String,
+    pub total: usize,
+    pub succeeded: Vec<String>,
+    /// Items that failed or timed out.
+    pub failed: Vec<String>,
+    /// Items cancelled, or skipped after a cancellation.
+    pub cancelled: Vec<String>,
+}
+
+/// Items of a per-item run still to be queued.
+struct Batch {
+    pending: VecDeque<Payload>,
+    limit: usize,
+    active: usize,
+    report: BatchReport,
+}
+
+/// Output of a running script, emitted as `job-output` line by line.
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct OutputLine {
+    pub job: u64,
+    /// "stdout" or "stderr".
+    pub stream: &'static str,
+    pub spans: Vec<Span>,
+    /// The line ended with a carriage return and the next line of the same
+    /// stream replaces it (progress bars).
+    pub replace: bool,
+}
+
+/// Batch file of a cmd script, removed when dropped.
+pub struct ScriptFile(PathBuf);
+
+impl Drop for ScriptFile {
+    fn drop(&mut self) {
+        fs::remove_file(&self.0).ok();
+    }
+}
+
+/// Output of a running job and the threads reading it.
+struct Output {
+    sink: Arc<Mutex<Sink>>,
+    readers: [thread::JoinHandle<()>; 2],
+}
+
+/// Where the output of a job goes while it runs.
+struct Sink {
+    log: Option<File>,
+    output: String,
+}
+
+#[derive(Default)]
+struct State {
+    queue: VecDeque<(u64, Payload)>,
+    /// Running job id → process (group) id, 0 until the process started.
+    running: HashMap<u64, u32>,
+    /// Running jobs the user asked to stop.
+    cancelled: HashSet<u64>,
+    batches: HashMap<u64, Batch>,
+}
+
+// endregion
+
+// region: Commands
+
+/// Return recent jobs, most recent last.
+#[tauri::command]
+pub fn list_jobs() -> Vec<Job> {
+    JOBS.lock().unwrap().iter().cloned().collect()
+}
+
+/// Stop a queued or running job.
+#[tauri::command]
+pub fn cancel_job(id: u64) -> Result<(), String> {
+    cancel(id)
+}
+
+/// Return the full output of a job, from its log file.
+#[tauri::command]
+pub fn get_job_log(id: u64) -> Result<String, String> {
+    let log = JOBS
+        .lock()
+        .unwrap()
+        .iter()
+        .find(|j| j.id == id)
+        .map(|j| j.log.clone())
+        .ok_or(format!("Unknown job {id}."))?;
+    fs::read_to_string(&log).map_err(|e| format!("Failed to read '{log}': {e}"))
+}
+
+/// Show the live log window for a job. Async so the window is not created on
+/// the main thread, which deadlocks on Windows.
+#[tauri::command]
+pub async fn open_job_log(id: u64) {
+    windows::open_log(id);
+}
+
+// endregion
+
+// region: Jobs
+
+/// Queue a script action and record it as a job. Up to `MAX_RUNNING`
+/// scripts run at once. Per-item modes queue one job per item, at most
+/// `worker_limit` at a time, and report once all items are done.
+pub fn spawn(info: &Payload) {
+    // Placeholders are rendered per run, quoted for the script's shell.
+    let quote = quote(info);
+    let render = |index: usize| {
+        template::render(&info.action, &info.context(index), quote).map(|action| Payload {
+            action,
+            ..info.clone()
+        })
+    };
+    if info.options.mode == ExecMode::Batch {
+        match render(0) {
+            Ok(info) => queue(info, None),
+            Err(e) => eprintln!("Command (script) - {e}"),
+        }
+        return;
+    }
+    let pending: Result<VecDeque<Payload>, String> = (0..info.items.len())
+        .map(|index| {
+            render(index).map(|rendered| Payload {
+                items: vec![info.items[index].clone()],
+                ..rendered
+            })
+        })
+        .collect();
+    let pending = match pending {
+        Ok(pending) => pending,
+        Err(e) => {
+            eprintln!("Command (script) - {e}");
+            return;
+        }
+    };
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    let batch = Batch {
+        pending,
+        limit: info.options.worker_limit(),
+        active: 0,
+        report: BatchReport {
+            id,
+            action: info.action.clone(),
+            total: info.items.len(),
+            ..BatchReport::default()
+        },
+    };
+    with_state(|state| state.batches.insert(id, batch));
+    feed(id);
+}
+
+/// Record a job and add it to the queue.
+fn queue(info: Payload, batch: Option<u64>) {
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    let queued_at = now();
+    let job = Job {
+        id,
+        action: info.action.clone(),
+        items: info.items.clone(),
+        status: JobStatus::Queued,
+        queued_at,
+        started_at: None,
+        ended_at: None,
+        exit_code: None,
+        output: String::new(),
+        log: log_path(id, queued_at).to_string_lossy().to_string(),
+        batch,
+    };
+    let log = job.log.clone();
+    update(job, "job-queued");
+    if info.options.log {
+        windows::open_log(id);
+    }
+    prune_logs(&log);
+    with_state(|state| state.queue.push_back((id, info)));
+    pump();
+}
+
+/// Queue the next items of a batch while it has free workers.
+fn feed(batch: u64) {
+    let next = with_state(|state| {
+        let mut next = Vec::new();
+        if let Some(batch) = state.batches.get_mut(&batch) {
+            while batch.active < batch.limit {
+                let Some(info) = batch.pending.pop_front() else {
+                    break;
+                };
+                batch.active += 1;
+                next.push(info);
+            }
+        }
+        next
+    });
+    for info in next {
+        queue(info, Some(batch));
+    }
+}
+
+/// Count a finished job in its batch, then continue or report.
+fn batch_done(batch: u64, item: String, status: JobStatus) {
+    let report = with_state(|state| {
+        let entry = state.batches.get_mut(&batch)?;
+        entry.active -= 1;
+        match status {
+            JobStatus::Succeeded => entry.report.succeeded.push(item),
+            JobStatus::Cancelled => entry.report.cancelled.push(item),
+            _ => entry.report.failed.push(item),
+        }
+        if entry.active > 0 || !entry.pending.is_empty() {
+            return None;
+        }
+        state.batches.remove(&batch).map(|b| b.report)
+    });
+    match report {
+        Some(report) => report_batch(&report),
+        None => feed(batch),
+    }
+}
+
+/// Stop a job: queued jobs are dropped, running ones have their whole
+/// process tree killed. Items of its batch not yet queued are skipped.
+pub fn cancel(id: u64) -> Result<(), String> {
+    let batch = JOBS
+        .lock()
+        .unwrap()
+        .iter()
+        .find(|j| j.id == id)
+        .and_then(|j| j.batch);
+    let action = with_state(|state| {
+        let action = if let Some(index) = state.queue.iter().position(|(queued, _)| *queued == id) {
+            state.queue.remove(index);
+            None
+        } else if let Some(pid) = state.running.get(&id).copied() {
+            state.cancelled.insert(id);
+            Some(pid)
+        } else {
+            return Err(format!("Job {id} is not queued or running."));
+        };
+        // Items of the batch not started yet are skipped too.
+        if let Some(batch) = batch.and_then(|b| state.batches.get_mut(&b)) {
+            let skipped = batch.pending.drain(..).flat_map(|p| p.items);
+            batch.report.cancelled.extend(skipped);
+        }
+        Ok(action)
+    })?;
+    match action {
+        // Not started yet, the process is killed as soon as it exists.
+        Some(0) => {}
+        Some(pid) => kill_tree(pid),
+        None => finish(id, JobStatus::Cancelled, None, String::new()),
+    }
+    println!("Jobs - Cancelled job {id}.");
+    Ok(())
+}
+
+/// Start queued jobs while there are free slots.
+fn pump() {
+    let started = with_state(|state| {
+        let mut started = Vec::new();
+        while state.running.len() < MAX_RUNNING {
+            let Some((id, info)) = state.queue.pop_front() else {
+                break;
+            };
+            state.running.insert(id, 0);
+            started.push((id, info));
+        }
+        started
+    });
+    for (id, info) in started {
+        // Run the script in a separate thread to avoid blocking & hanging.
+        thread::spawn(move || {
+            let (status, exit_code, output) = run(id, &info);
+            with_state(|state| {
+                state.running.remove(&id);
+                state.cancelled.remove(&id);
+            });
+            finish(id, status, exit_code, output);
+            pump();
+        });
+    }
+}
+
+/// Execute a script with the selected files as arguments, with timeout
+/// protection. Returns the status, exit code and output.
+fn run(id: u64, info: &Payload) -> (JobStatus, Option<i32>, String) {
+    edit(id, "job-started", |job| {
+        job.status = JobStatus::Running;
+        job.started_at = Some(now());
+    });
+    let (mut command, _script) = match script_command(info) {
+        Ok(command) => command,
+        Err(e) => {
+            eprintln!("Command (script) - {e}");
+            return (JobStatus::Failed, None, e);
+        }
+    };
+    command
+        .current_dir(working_dir(info))
+        .envs(&info.options.env)
+        .envs(info.input_env())
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    let mut child = match command.spawn() {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("Command (script) - Failed to execute script: {e}");
+            return (JobStatus::Failed, None, format!("Failed to start: {e}"));
+        }
+    };
+    // Cancellation may have been requested before the process existed.
+    let cancelled = with_state(|state| {
+        state.running.insert(id, child.id());
+        state.cancelled.contains(&id)
+    });
+    if cancelled {
+        kill_tree(child.id());
+    }
+    // Drain output while waiting so a chatty script cannot fill the pipes.
+    let output = collect_output(id, &mut child);
+    let timeout = info.options.timeout.unwrap_or(SCRIPT_TIMEOUT);
+    let waited = match timeout {
+        0 => child.wait().map(Some),
+        seconds => child.wait_timeout(Duration::from_secs(seconds)),
+    };
+    let result = match waited {
+        Ok(Some(status)) if status.success() => (JobStatus::Succeeded, status.code()),
+        Ok(Some(status)) => (JobStatus::Failed, status.code()),
+        Ok(None) => {
+            kill_tree(child.id());
+            child.wait().ok();
+            eprintln!("Command (script) - Execution took too long (timeout).");
+            (JobStatus::TimedOut, None)
+        }
+        Err(e) => {
+            eprintln!("Command (script) - Failed while waiting for command: {e}");
+            (JobStatus::Failed, None)
+        }
+    };
+    // Processes left in the background may hold the pipes open.
+    wait_for_pipes(child.id(), || {
+        output.readers.iter().all(|r| r.is_finished())
+    });
+    let output = std::mem::take(&mut output.sink.lock().unwrap().output)
+        .trim()
+        .to_string();
+    if with_state(|state| state.cancelled.remove(&id)) {
+        return (JobStatus::Cancelled, None, output);
+    }
+    (result.0, result.1, output)
+}
+
+/// Record the result of a job and notify the user, or count it in its
+/// batch.
+fn finish(id: u64, status: JobStatus, exit_code: Option<i32>, output: String) {
+    let job = edit(id, "job-finished", |job| {
+        job.status = status;
+        job.exit_code = exit_code;
+        job.output = truncate(output);
+        job.ended_at = Some(now());
+    });
+    let Some(job) = job else {
+        return;
+    };
+    match job.batch {
+        Some(batch) => batch_done(batch, job.items.concat(), status),
+        None => notify(&job),
+    }
+}
+
+// endregion
+
+// region: Utils
+
+/// Stream stdout and stderr on background threads: each line is emitted to
+/// the frontend and written to the job log.
+fn collect_output(id: u64, child: &mut Child) -> Output {
+    let log = JOBS
+        .lock()
+        .unwrap()
+        .iter()
+        .find(|j| j.id == id)
+        .and_then(|j| File::create(&j.log).ok());
+    let sink = Arc::new(Mutex::new(Sink {
+        log,
+        output: String::new(),
+    }));
+    let stdout = child
+        .stdout
+        .take()
+        .map(|p| Box::new(p) as Box<dyn Read + Send>);
+    let stderr = child
+        .stderr
+        .take()
+        .map(|p| Box::new(p) as Box<dyn Read + Send>);
+    let readers = [("stdout", stdout), ("stderr", stderr)].map(|(stream, pipe)| {
+        let sink = sink.clone();
+        thread::spawn(move || {
+            if let Some(pipe) = pipe {
+                stream_lines(id, stream, pipe, &sink);
+            }
+        })
+    });
+    Output { sink, readers }
+}
+
+/// Wait until the output pipes of an exited process are closed. Background
+/// processes still holding them after `PIPE_GRACE` are killed, and the wait
+/// ends after another `PIPE_GRACE` in any case. Returns whether the pipes
+/// were drained.
+pub fn wait_for_pipes(pid: u32, drained: impl Fn() -> bool) -> bool {
+    let wait = || {
+        let deadline = Instant::now() + PIPE_GRACE;
+        while !drained() {
+            if Instant::now() >= deadline {
+                return false;
+            }
+            thread::sleep(Duration::from_millis(20));
+        }
+        true
+    };
+    if wait() {
+        return true;
+    }
+    eprintln!("Jobs - Killing background processes of {pid} holding its output.");
+    kill_tree(pid);
+    wait()
+}
+
+/// Read a pipe until it closes, splitting lines on `\n` and `\r`.
+fn stream_lines(id: u64, stream: &'static str, mut pipe: Box<dyn Read + Send>, sink: &Mutex<Sink>) {
+    let app = crate::get_app_handle().clone();
+    let mut style = Style::default();
+    let mut pending: Vec<u8> = Vec::new();
+    let mut buffer = [0u8; 8192];
+    let mut emit_line = |bytes: &[u8], replace: bool| {
+        let line = String::from_utf8_lossy(bytes);
+        let spans = ansi::parse(&line, &mut style);
+        // Progress updates are shown live but only the final state is kept.
+        if !replace {
+            let text: String = spans.iter().map(|s| s.text.as_str()).collect();
+            let mut sink = sink.lock().unwrap();
+            if let Some(log) = sink.log.as_mut() {
+                writeln!(log, "{text}").ok();
+            }
+            sink.output.push_str(&text);
+            sink.output.push('\n');
+            if sink.output.len() > OUTPUT_LIMIT * 2 {
+                sink.output = truncate(std::mem::take(&mut sink.output));
+            }
+        }
+        let line = OutputLine {
+            job: id,
+            stream,
+            spans,
+            replace,
+        };
+        app.emit("job-output", &line).ok();
+    };
+    loop {
+        let read = match pipe.read(&mut buffer) {
+            Ok(0) | Err(_) => break,
+            Ok(read) => read,
+        };
+        pending.extend_from_slice(&buffer[..read]);
+        let mut start = 0;
+        let mut index = 0;
+        while index < pending.len() {
+            match pending[index] {
+                b'\n' => {
+                    let line = &pending[start..index];
+                    emit_line(line.strip_suffix(b"\r").unwrap_or(line), false);
+                    start = index + 1;
+                }
+                // A lone `\r` rewrites the line, `\r\n` ends it.
+                b'\r' if index + 1 < pending.len() && pending[index + 1] != b'\n' => {
+                    emit_line(&pending[start..index], true);
+                    start = index + 1;
+                }
+                _ => {}
+            }
+            index += 1;
+        }
+        pending.drain(..start);
+    }
+    let rest = pending.strip_suffix(b"\r").unwrap_or(&pending);
+    if !rest.is_empty() {
+        emit_line(rest, false);
+    }
+}
+
+/// Store a new job and emit it to the frontend. Beyond `HISTORY_LIMIT`,
+/// the oldest finished jobs are dropped; queued and running jobs, and the
+/// jobs of unfinished batches, are kept.
+fn update(job: Job, event: &str) {
+    let live_batches: HashSet<u64> = with_state(|state| state.batches.keys().copied().collect());
+    {
+        let mut jobs = JOBS.lock().unwrap();
+        jobs.push_back(job.clone());
+        let mut overflow = jobs.len().saturating_sub(HISTORY_LIMIT);
+        jobs.retain(|j| {
+            let live = matches!(j.status, JobStatus::Queued | JobStatus::Running)
+                || j.batch.is_some_and(|b| live_batches.contains(&b));
+            if overflow == 0 || live {
+                return true;
+            }
+            overflow -= 1;
+            false
+        });
+    }
+    emit(event, &job);
+}
+
+/// Change a recorded job and emit it to the frontend.
+fn edit(id: u64, event: &str, f: impl FnOnce(&mut Job)) -> Option<Job> {
+    let job = {
+        let mut jobs = JOBS.lock().unwrap();
+        let job = jobs.iter_mut().find(|j| j.id == id)?;
+        f(job);
+        job.clone()
+    };
+    emit(event, &job);
+    Some(job)
+}
+
+fn emit(event: &str, job: &Job) {
+    let app = crate::get_app_handle().clone();
+    if let Err(e) = app.emit(event, job) {
+        eprintln!("Jobs - Failed to emit '{event}': {e}");
+    }
+}
+
+/// Show a native notification for a finished job.
+fn notify(job: &Job) {
+    let app = crate::get_app_handle().clone();
+    let title = match job.status {
+        JobStatus::Succeeded => "Script finished",
+        JobStatus::TimedOut => "Script timed out",
+        JobStatus::Cancelled => "Script cancelled",
+        _ => "Script failed",
+    };
+    let body = match (job.output.lines().last(), job.status, job.exit_code) {
+        (_, JobStatus::TimedOut, _) => "Stopped after reaching its time limit.".to_string(),
+        (Some(line), _, _) => line.to_string(),
+        (None, _, Some(code)) => format!("Exited with code {code}."),
+        (None, _, None) => "Terminated.".to_string(),
+    };
+    println!("Jobs - Job {} ({:?}): {}", job.id, job.status, body);
+    if let Err(e) = app.notification().builder().title(title).body(body).show() {
+        eprintln!("Jobs - Failed to show notification: {e}");
+    }
+}
+
+/// Build the command running a script with the selected items as arguments.
+/// Scripts get their own process group so they can be killed along with
+/// their children. cmd scripts are written to a batch file, where quoted
+/// values keep their `%`, and which is removed when the returned
+/// `ScriptFile` is dropped.
+pub fn script_command(info: &Payload) -> Result<(Command, Option<ScriptFile>), String> {
+    let action = info.action.as_str();
+    let mut command;
+    let mut script = None;
+    match shell(info) {
+        "pwsh" => {
+            // Extra `-Command` arguments are appended to the script text, so
+            // items are passed through the environment instead. Without items
+            // the script gets no arguments rather than an empty one.
+            command = Command::new("pwsh");
+            command.args(["-NoProfile", "-NonInteractive", "-Command"]);
+            if info.items.is_empty() {
+                command.arg(format!("& {{ {action} }}"));
+            } else {
+                command
+                    .arg(format!(
+                        "& {{ {action} }} @($env:TOOLY_ITEMS -split \"`n\")"
+                    ))
+                    .env("TOOLY_ITEMS", info.items.join("\n"));
+            }
+        }
+        "python" => {
+            command = Command::new(if cfg!(windows) { "python" } else { "python3" });
+            command.arg("-c").arg(action).args(&info.items);
+        }
+        "cmd" => {
+            let content = format!("@echo off\r\nchcp 65001 >nul\r\n{action}\r\n");
+            let path = terminal::create_script("bat", &content)
+                .map_err(|e| format!("Failed to write script: {e}"))?;
+            // Arguments of batch files are escaped by the standard library.
+            command = Command::new(&path);
+            command.args(&info.items);
+            script = Some(ScriptFile(path));
+        }
+        "zsh" => {
+            command = Command::new("zsh");
+            command.args(["-f", "-c", action, "--"]).args(&info.items);
+        }
+        "sh" => {
+            command = Command::new("sh");
+            command.args(["-c", action, "--"]).args(&info.items);
+        }
+        _ => {
+            command = Command::new("bash");
+            command
+                .args(["--noprofile", "--norc", "-c", action, "--"])
+                .args(&info.items);
+        }
+    }
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+        command.creation_flags(CREATE_NO_WINDOW);
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        use std::os::unix::process::CommandExt;
+        command.process_group(0);
+    }
+    Ok((command, script))
+}
+
+/// Shell of a script: cmd on Windows and bash elsewhere unless set.
+fn shell(info: &Payload) -> &str {
+    match info.options.shell.as_str() {
+        "" if cfg!(windows) => "cmd",
+        "" => "bash",
+        shell => shell,
+    }
+}
+
+/// Quoting of placeholder values for the script's shell.
+pub fn quote(info: &Payload) -> Quote {
+    match shell(info) {
+        "pwsh" => Quote::PowerShell,
+        "python" => Quote::Python,
+        "cmd" => Quote::Cmd,
+        _ => Quote::Posix,
+    }
+}
+
+/// Resolve the working directory of a script from its `cwd` option.
+pub fn working_dir(info: &Payload) -> PathBuf {
+    match info.options.cwd.as_str() {
+        "" | "target" => PathBuf::from(&info.target),
+        "parent" => info
+            .items
+            .first()
+            .and_then(|item| Path::new(item).parent())
+            .map(Path::to_path_buf)
+            .unwrap_or_else(|| PathBuf::from(&info.target)),
+        path => PathBuf::from(path),
+    }
+}
+
+/// Return the log file of a job (`logs/<queued at>-<id>.log`).
+fn log_path(id: u64, queued_at: u64) -> PathBuf {
+    let app = crate::get_app_handle();
+    let dir = app.path().app_data_dir().unwrap().join("logs");
+    fs::create_dir_all(&dir).ok();
+    dir.join(format!("{queued_at}-{id}.log"))
+}
+
+/// Remove the oldest logs next to `log`, keeping `LOG_LIMIT` files.
+fn prune_logs(log: &str) {
+    let Some(dir) = Path::new(log).parent() else {
+        return;
+    };
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    let mut logs: Vec<PathBuf> = entries.flatten().map(|e| e.path()).collect();
+    // Names start with the queue time, so they sort oldest first.
+    logs.sort_by_key(|path| {
+        path.file_name()
+            .and_then(|n| n.to_str())
+            .and_then(|n| n.split('-').next())
+            .and_then(|n| n.parse::<u64>().ok())
+            .unwrap_or(0)
+    });
+    let overflow = logs.len().saturating_sub(LOG_LIMIT);
+    for path in &logs[..overflow] {
+        fs::remove_file(path).ok();
+    }
+}
+
+/// Kill a process and all of its children.
+pub fn kill_tree(pid: u32) {
+    #[cfg(target_os = "windows")]
+    let result = {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+        Command::new("taskkill")
+            .args(["/T", "/F", "/PID", &pid.to_string()])
+            .creation_flags(CREATE_NO_WINDOW)
+            .status()
+    };
+    // Scripts lead their own process group, a negative id targets it.
+    #[cfg(not(target_os = "windows"))]
+    let result = Command::new("kill")
+        .args(["-KILL", "--", &format!("-{pid}")])
+        .status();
+    if let Err(e) = result {
+        eprintln!("Jobs - Failed to kill process {pid}: {e}");
+    }
+}
+
+fn with_state<T>(f: impl FnOnce(&mut State) -> T) -> T {
+    f(STATE.lock().unwrap().get_or_insert_with(State::default))
+}
+
+/// Emit a batch summary and show it as a notification.
+fn report_batch(report: &BatchReport) {
+    let app = crate::get_app_handle().clone();
+    app.emit("batch-finished", report).ok();
+    let title = if report.failed.is_empty() && report.cancelled.is_empty() {
+        "Script finished"
+    } else {
+        "Script finished with errors"
+    };
+    let mut body = format!(
+        "{} of {} item(s) succeeded.",
+        report.succeeded.len(),
+        report.total
+    );
+    if !report.failed.is_empty() {
+        body.push_str(&format!(" Failed: {}.", file_names(&report.failed)));
+    }
+    if !report.cancelled.is_empty() {
+        body.push_str(&format!(" Cancelled: {}.", report.cancelled.len()));
+    }
+    println!("Jobs - Batch {}: {}", report.id, body);
+    if let Err(e) = app.notification().builder().title(title).body(body).show() {
+        eprintln!("Jobs - Failed to show notification: {e}");
+    }
+}
+
+/// Short list of file names for notifications.
+fn file_names(paths: &[String]) -> String {
+    let names: Vec<String> = paths
+        .iter()
+        .take(3)
+        .map(|p| {
+            Path::new(p)
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_else(|| p.clone())
+        })
+        .collect();
+    match paths.len() {
+        0..=3 => names.join(", "),
+        n => format!("{} and {} more", names.join(", "), n - 3),
+    }
+}
+
+/// Keep the end of the output, where errors usually are.
+fn truncate(output: String) -> String {
+    if output.len() <= OUTPUT_LIMIT {
+        return output;
+    }
+    let mut start = output.len() - OUTPUT_LIMIT;
+    while !output.is_char_boundary(start) {
+        start += 1;
+    }
+    format!("…{}", &output[start..])
+}
+
+/// Current time in milliseconds.
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+// endregion
@@ -0,0 +1,295 @@
+use crate::get_app_handle;
+use crate::rename;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use std::{fs, path::Path, path::PathBuf};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons};
+
+// region: Variables
+
+/// Maximum number of operations kept in the journal.
+const JOURNAL_LIMIT: usize = 200;
+
+/// Serializes journal reads and writes across threads.
+static JOURNAL_LOCK: Mutex<()> = Mutex::new(());
+
+/// Maximum number of operations reverted by one `tooly://undo` link.
+const UNDO_LIMIT: usize = 20;
+
+// endregion
+
+// region: Structs
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum OperationKind {
+    Create,
+    Rename,
+}
+
+/// A single user action, possibly touching several files.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Operation {
+    id: u64,
+    kind: OperationKind,
+    entries: Vec<Entry>,
+}
+
+/// One file touched by an operation, with a fingerprint of the result so
+/// undo can detect later changes.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Entry {
+    /// Original path (empty for created files).
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    from: String,
+    to: String,
+    size: u64,
+    modified: u128,
+}
+
+// endregion
+
+// region: Commands
+
+/// Revert the last `count` operations (defaults to one).
+#[tauri::command]
+pub fn undo_operations(count: Option<usize>) -> Result<Vec<Operation>, String> {
+    undo(count.unwrap_or(1))
+}
+
+/// Return the recorded operations, most recent last.
+#[tauri::command]
+pub fn list_operations() -> Vec<Operation> {
+    let _lock = JOURNAL_LOCK.lock().unwrap();
+    load()
+}
+
+/// Ask for confirmation, then revert the last `count` operations (capped
+/// at `UNDO_LIMIT`). Used by `tooly://undo`, which anyone can open.
+pub fn trigger_undo(app: &AppHandle, count: usize) {
+    let app = app.clone();
+    let count = count.clamp(1, UNDO_LIMIT);
+    // The confirmation dialog blocks and must not run on the main thread.
+    std::thread::spawn(move || {
+        let pending: Vec<String> = {
+            let _lock = JOURNAL_LOCK.lock().unwrap();
+            load().iter().rev().take(count).map(describe).collect()
+        };
+        if pending.is_empty() {
+            println!("Journal - Nothing to undo.");
+            return;
+        }
+        let confirmed = app
+            .dialog()
+            .message(format!("Undo these operations?\n\n{}", pending.join("\n")))
+            .title("Undo")
+            .buttons(MessageDialogButtons::OkCancelCustom(
+                "Undo".to_string(),
+                "Cancel".to_string(),
+            ))
+            .blocking_show();
+        if !confirmed {
+            println!("Journal - Undo cancelled by user.");
+            return;
+        }
+        if let Err(e) = undo(pending.len()) {
+            eprintln!("Journal - Failed to undo: {e}");
+            app.dialog().message(e).title("Undo").blocking_show();
+        }
+    });
+}
+
+// endregion
+
+// region: Journal
+
+/// Record a file created by an action.
+pub fn record_create(path: &Path) {
+    record(
+        OperationKind::Create,
+        &[(PathBuf::new(), path.to_path_buf())],
+    );
+}
+
+/// Record an operation made of (from, to) pairs.
+pub fn record(kind: OperationKind, pairs: &[(PathBuf, PathBuf)]) {
+    if pairs.is_empty() {
+        return;
+    }
+    let entries = pairs
+        .iter()
+        .map(|(from, to)| {
+            let (size, modified) = fingerprint(to);
+            Entry {
+                from: from.to_string_lossy().to_string(),
+                to: to.to_string_lossy().to_string(),
+                size,
+                modified,
+            }
+        })
+        .collect();
+    let _lock = JOURNAL_LOCK.lock().unwrap();
+    let mut operations = load();
+    operations.push(Operation {
+        id: now(),
+        kind,
+        entries,
+    });
+    let overflow = operations.len().saturating_sub(JOURNAL_LIMIT);
+    operations.drain(..overflow);
+    if let Err(e) = save(&operations) {
+        eprintln!("Journal - Failed to save journal: {e}");
+    }
+}
+
+/// Revert the last `count` operations, most recent first. Stops at the first
+/// operation whose files changed since it was recorded.
+pub fn undo(count: usize) -> Result<Vec<Operation>, String> {
+    let _lock = JOURNAL_LOCK.lock().unwrap();
+    let mut operations = load();
+    let mut reverted = Vec::new();
+    let mut result = Ok(());
+    for _ in 0..count {
+        let Some(operation) = operations.last() else {
+            break;
+        };
+        if let Err(e) = revert(operation) {
+            result = Err(e);
+            break;
+        }
+        reverted.push(operations.pop().unwrap());
+    }
+    if !reverted.is_empty() {
+        save(&operations).map_err(|e| format!("Failed to save journal: {e}"))?;
+        println!("Journal - Reverted {} operation(s).", reverted.len());
+    }
+    result.map(|_| reverted)
+}
+
+/// Revert a single operation after checking none of its files changed.
+fn revert(operation: &Operation) -> Result<(), String> {
+    let done = match operation.kind {
+        OperationKind::Create => "created",
+        OperationKind::Rename => "renamed",
+    };
+    let targets: Vec<&str> = operation.entries.iter().map(|e| e.to.as_str()).collect();
+    for entry in &operation.entries {
+        let to = Path::new(&entry.to);
+        if !to.exists() {
+            return Err(format!("'{}' no longer exists.", entry.to));
+        }
+        if fingerprint(to) != (entry.size, entry.modified) {
+            return Err(format!("'{}' changed since it was {done}.", entry.to));
+        }
+        // The original name must be free, unless another entry vacates it.
+        if !entry.from.is_empty()
+            && Path::new(&entry.from).exists()
+            && !targets.contains(&entry.from.as_str())
+        {
+            return Err(format!("'{}' already exists.", entry.from));
+        }
+    }
+    match operation.kind {
+        OperationKind::Create => {
+            for entry in &operation.entries {
+                let to = Path::new(&entry.to);
+                // Only empty folders are removed.
+                let removed = if to.is_dir() {
+                    fs::remove_dir(to)
+                } else {
+                    fs::remove_file(to)
+                };
+                removed.map_err(|e| format!("Failed to remove '{}': {e}", entry.to))?;
+            }
+            Ok(())
+        }
+        OperationKind::Rename => {
+            let pairs: Vec<(PathBuf, PathBuf)> = operation
+                .entries
+                .iter()
+                .map(|e| (PathBuf::from(&e.to), PathBuf::from(&e.from)))
+                .collect();
+            rename::move_all(&pairs)
+        }
+    }
+}
+
+// endregion
+
+// region: Utils
+
+/// Return the journal file path (next to `config.json`).
+fn journal_path() -> PathBuf {
+    let app = get_app_handle();
+    app.path().app_data_dir().unwrap().join("journal.json")
+}
+
+/// Load the journal, starting fresh if it is missing or unreadable.
+fn load() -> Vec<Operation> {
+    fs::read_to_string(journal_path())
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+/// Write the journal through a temporary file so it is never half written.
+fn save(operations: &[Operation]) -> std::io::Result<()> {
+    let path = journal_path();
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let temp = path.with_extension("json.tmp");
+    fs::write(&temp, serde_json::to_string_pretty(operations)?)?;
+    fs::rename(&temp, &path)
+}
+
+/// Return the size and modification time (ms) of a path.
+fn fingerprint(path: &Path) -> (u64, u128) {
+    let Ok(metadata) = fs::metadata(path) else {
+        return (0, 0);
+    };
+    let size = if metadata.is_dir() { 0 } else { metadata.len() };
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_millis())
+        .unwrap_or(0);
+    (size, modified)
+}
+
+/// Short description of an operation for the undo confirmation.
+fn describe(operation: &Operation) -> String {
+    let name = |path: &str| {
+        Path::new(path)
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string()
+    };
+    let first = operation
+        .entries
+        .first()
+        .map(|e| name(&e.to))
+        .unwrap_or_default();
+    let others = match operation.entries.len() {
+        0 | 1 => String::new(),
+        n => format!(" and {} more", n - 1),
+    };
+    match operation.kind {
+        OperationKind::Create => format!("Created '{first}'{others}"),
+        OperationKind::Rename => format!("Renamed to '{first}'{others}"),
+    }
+}
+
+/// Current time in milliseconds, used as operation id.
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+// endregion
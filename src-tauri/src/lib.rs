@@ -2,9 +2,29 @@ use once_cell::sync::OnceCell;
 use std::{fs, sync::Mutex};
 use tauri::tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent};
 use tauri::{App, AppHandle, Manager, Url, WindowEvent};
-use tauri_plugin_global_shortcut::{
-    Code, GlobalShortcutExt, Modifiers, Shortcut, ShortcutEvent, ShortcutState,
-};
+use tauri_plugin_global_shortcut::{Shortcut, ShortcutEvent, ShortcutState};
+mod ansi;
+mod clipboard;
+mod color;
+mod create;
+mod explorer;
+mod jobs;
+mod journal;
+#[cfg(target_os = "linux")]
+mod linux_menu;
+mod palettes;
+mod payload;
+mod policy;
+mod privacy;
+mod prompt;
+mod rename;
+mod settings;
+mod shortcuts;
+mod template;
+mod templates;
+mod terminal;
+mod transfer;
+mod transforms;
 mod uninstall;
 mod utils;
 mod windows;
@@ -43,15 +63,18 @@ pub fn run() {
     // Initialize shell plugin.
     builder = builder.plugin(tauri_plugin_shell::init());
 
+    // Initialize notification plugin.
+    builder = builder.plugin(tauri_plugin_notification::init());
+
     // Initialize clipboard manager plugin.
     builder = builder.plugin(tauri_plugin_clipboard_manager::init());
 
     // Initialize global shortcut plugin.
     builder = builder.plugin(
         tauri_plugin_global_shortcut::Builder::new()
-            .with_handler(move |_app, shortcut, event| {
+            .with_handler(move |app, shortcut, event| {
                 // Initialize shortcut handler.
-                handle_shortcuts(shortcut, event);
+                handle_shortcuts(app, shortcut, event);
             })
             .build(),
     );
@@ -61,15 +84,31 @@ pub fn run() {
         APP_HANDLE
             .set(Mutex::new(app.app_handle().to_owned()))
             .unwrap();
+        // Create the key used to verify payloads from the extension.
+        payload::ensure_key(app.app_handle());
+        // Remove terminal scripts of earlier runs.
+        terminal::clean_scripts();
+        // Record clipboard history.
+        clipboard::watch(app.app_handle());
         // Set app policy (Make it not show on dock/taskbar). macOS only.
         #[cfg(target_os = "macos")]
         set_policy(app);
-        // Register all shortcuts (Make them dynamic).
+        // Register all shortcuts from settings.
         register_shortcuts(app);
         // Create tray icon.
         create_tray(app);
         // Re-enable the Finder Sync extension if a previous uninstall disabled it.
         uninstall::ensure_extension_enabled();
+        // Generate file manager context menu entries (Linux & Windows).
+        #[cfg(target_os = "linux")]
+        linux_menu::sync(app.app_handle());
+        #[cfg(target_os = "windows")]
+        if let Err(e) = explorer::install(app.app_handle()) {
+            eprintln!("Execution - Failed to update Explorer menu: {e}");
+        }
+        // Listen to deep links (macOS receives them through run events).
+        #[cfg(any(windows, target_os = "linux"))]
+        register_deep_links(app);
         // Handle execution.
         let args: Vec<String> = std::env::args().collect();
         handle_execution(app.app_handle(), args);
@@ -82,11 +121,58 @@ pub fn run() {
         if let WindowEvent::CloseRequested { api, .. } = event {
             api.prevent_close();
             window.hide().unwrap();
+            // Closing the prompt window cancels the action waiting on it.
+            if window.label() == "prompt" {
+                prompt::cancel();
+            }
         }
     });
 
     // Register invokable commands.
-    builder = builder.invoke_handler(tauri::generate_handler![uninstall::uninstall_tooly]);
+    builder = builder.invoke_handler(tauri::generate_handler![
+        uninstall::uninstall_tooly,
+        clipboard::list_clipboard,
+        clipboard::get_clipboard_text,
+        clipboard::get_clipboard_image,
+        clipboard::copy_clipboard_entry,
+        clipboard::pin_clipboard_entry,
+        clipboard::delete_clipboard_entry,
+        clipboard::clear_clipboard_history,
+        transforms::list_clipboard_transforms,
+        transforms::run_clipboard_transform,
+        palettes::get_palettes,
+        palettes::save_palette,
+        palettes::remove_palette,
+        palettes::add_recent_color,
+        palettes::clear_recent_colors,
+        palettes::convert_color,
+        palettes::copy_color,
+        palettes::pick_color,
+        palettes::export_palette,
+        rename::rename_selection,
+        rename::rename_preview,
+        rename::rename_apply,
+        journal::undo_operations,
+        jobs::list_jobs,
+        jobs::cancel_job,
+        jobs::get_job_log,
+        jobs::open_job_log,
+        prompt::get_prompt,
+        prompt::submit_prompt,
+        journal::list_operations,
+        shortcuts::reload_shortcuts,
+        settings::get_settings,
+        settings::save_settings,
+        templates::list_templates,
+        terminal::list_terminals,
+        templates::add_template,
+        templates::remove_template,
+        transfer::export_settings,
+        transfer::import_settings,
+        transfer::reset_settings,
+        explorer::install_explorer_menu,
+        explorer::uninstall_explorer_menu
+    ]);
 
     // Finalize build and run.
     builder
@@ -121,11 +207,15 @@ fn is_first_run(app: &AppHandle) -> bool {
     }
     fs::create_dir_all(&dir).unwrap();
     fs::write(&path, "{}").unwrap();
+    // Create the menu settings shared with the Finder extension.
+    settings::ensure_exists(app);
     true
 }
 
 /// Handle execution logic and arguments.
-fn handle_execution(app: &AppHandle, _args: Vec<String>) {
+fn handle_execution(app: &AppHandle, args: Vec<String>) {
+    // Run menu items requested from the command line (Explorer).
+    utils::execute_args(&args);
     // Check if it is first time running app.
     if is_first_run(app) {
         println!("Execution - First time running application.");
@@ -136,8 +226,12 @@ fn handle_execution(app: &AppHandle, _args: Vec<String>) {
 }
 
 /// Handle app reopen event and single instance arguments.
-fn handle_reopen(_app: &AppHandle, _args: Vec<String>) {
-    println!("Execution - App reopened (Arguments: '{:?}')", _args);
+fn handle_reopen(_app: &AppHandle, args: Vec<String>) {
+    println!("Execution - App reopened (Arguments: '{:?}')", args);
+    // Menu items started from the file manager should not show the window.
+    if utils::execute_args(&args) {
+        return;
+    }
     windows::open_main();
 }
 
@@ -148,10 +242,11 @@ fn handle_url(url: Url) {
 }
 
 /// Handle global shortcut events.
-fn handle_shortcuts(shortcut: &Shortcut, event: ShortcutEvent) {
+fn handle_shortcuts(app: &AppHandle, shortcut: &Shortcut, event: ShortcutEvent) {
     match event.state() {
         ShortcutState::Pressed => {
             println!("Shortcut ({:?}) Pressed!", shortcut);
+            shortcuts::dispatch(app, shortcut);
         }
         ShortcutState::Released => {
             println!("Shortcut ({:?}) Released!", shortcut);
@@ -159,11 +254,10 @@ fn handle_shortcuts(shortcut: &Shortcut, event: ShortcutEvent) {
     }
 }
 
-/// Register all global shortcuts.
+/// Register all global shortcuts and keep them in sync with settings.
 fn register_shortcuts(app: &mut App) {
-    // TODO: Make shortcuts dynamic via config.
-    let ctrl_n_shortcut = Shortcut::new(Some(Modifiers::CONTROL), Code::KeyN);
-    app.global_shortcut().register(ctrl_n_shortcut).unwrap();
+    shortcuts::register_all(app.app_handle());
+    settings::watch(app.app_handle());
 }
 
 /// Create tray icon.
@@ -195,6 +289,28 @@ fn create_tray(app: &mut App) {
         .ok();
 }
 
+/// Register the `tooly://` scheme and forward deep links (Windows & Linux).
+#[cfg(any(windows, target_os = "linux"))]
+fn register_deep_links(app: &mut App) {
+    use tauri_plugin_deep_link::DeepLinkExt;
+    let deep_link = app.deep_link();
+    // Make sure the scheme points at this executable (dev builds, AppImages).
+    if let Err(e) = deep_link.register_all() {
+        eprintln!("Execution - Failed to register deep links: {e}");
+    }
+    // Links passed on launch, then links forwarded by new instances.
+    if let Ok(Some(urls)) = deep_link.get_current() {
+        for url in urls {
+            handle_url(url);
+        }
+    }
+    deep_link.on_open_url(|event| {
+        for url in event.urls() {
+            handle_url(url);
+        }
+    });
+}
+
 /// Set application activation policy (macOS only).
 #[cfg(target_os = "macos")]
 fn set_policy(app: &mut App) {
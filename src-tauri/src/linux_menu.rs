@@ -0,0 +1,451 @@
+use crate::settings::{self, MenuItem, MenuSettings};
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::{env, fs};
+use tauri::{AppHandle, Manager};
+
+// region: Variables
+
+/// Folder name used for Tooly entries inside file manager script folders.
+const MENU_FOLDER: &str = "Tooly";
+
+/// Prefix of Tooly actions inside Thunar's `uca.xml`.
+const THUNAR_ID_PREFIX: &str = "tooly-";
+
+/// Items sharing a Dolphin service menu file.
+type MenuEntries<'a> = Vec<(&'a String, &'a MenuItem)>;
+
+/// Lowercase glob patterns and their MIME types.
+type MimeGlobs = Vec<(String, String)>;
+
+/// MIME type of files whose extension is not in the shared MIME database.
+const UNKNOWN_MIME_TYPE: &str = "application/octet-stream";
+
+// endregion
+
+// region: Sync
+
+/// Regenerate Nautilus, Nemo, Dolphin and Thunar entries from settings.
+/// Invalid settings leave the current entries in place.
+pub fn sync(app: &AppHandle) {
+    let settings = match settings::load(app) {
+        Ok(settings) => settings,
+        Err(e) => {
+            eprintln!("Linux Menu - Kept file manager entries: {e}");
+            return;
+        }
+    };
+    let Ok(home) = app.path().home_dir() else {
+        eprintln!("Linux Menu - No home folder, skipping file manager entries.");
+        return;
+    };
+    let Some(exe) = executable() else {
+        eprintln!("Linux Menu - Unknown executable path, skipping file manager entries.");
+        return;
+    };
+    let scripts_dir = settings::settings_dir(app).join("menu");
+    let result = write_scripts(&settings, &scripts_dir, &exe)
+        .and_then(|_| {
+            write_script_folder(&settings, &home.join(".local/share/nautilus/scripts"), &exe)
+        })
+        .and_then(|_| write_script_folder(&settings, &home.join(".local/share/nemo/scripts"), &exe))
+        .and_then(|_| {
+            write_service_menus(
+                &settings,
+                &scripts_dir,
+                &home.join(".local/share/kio/servicemenus"),
+            )
+        })
+        .and_then(|_| {
+            write_thunar_actions(
+                &settings,
+                &scripts_dir,
+                &home.join(".config/Thunar/uca.xml"),
+            )
+        });
+    match result {
+        Ok(_) => println!("Linux Menu - Updated file manager entries."),
+        Err(e) => eprintln!("Linux Menu - Failed to update file manager entries: {e}"),
+    }
+}
+
+/// Write one launcher script per enabled item, used by Dolphin and Thunar.
+fn write_scripts(settings: &MenuSettings, dir: &Path, exe: &Path) -> std::io::Result<()> {
+    recreate_dir(dir)?;
+    for (id, _) in enabled_items(settings) {
+        write_executable(&dir.join(script_name(id)), &item_script(id, exe))?;
+    }
+    Ok(())
+}
+
+/// Write Nautilus/Nemo scripts, one sub folder per group (shown as submenus).
+fn write_script_folder(
+    settings: &MenuSettings,
+    scripts_root: &Path,
+    exe: &Path,
+) -> std::io::Result<()> {
+    // Only touch file managers that are installed (their folder exists).
+    if !scripts_root.parent().is_some_and(Path::exists) {
+        return Ok(());
+    }
+    let dir = scripts_root.join(MENU_FOLDER);
+    recreate_dir(&dir)?;
+    for (id, item) in enabled_items(settings) {
+        let folder = if item.group.is_empty() {
+            dir.clone()
+        } else {
+            dir.join(file_name(&item.group))
+        };
+        fs::create_dir_all(&folder)?;
+        write_executable(&folder.join(file_name(id)), &item_script(id, exe))?;
+    }
+    Ok(())
+}
+
+/// Write Dolphin service menus. Mime types are per file, so items are split
+/// into one file per (group, target type) pair.
+fn write_service_menus(
+    settings: &MenuSettings,
+    scripts_dir: &Path,
+    dir: &Path,
+) -> std::io::Result<()> {
+    fs::create_dir_all(dir)?;
+    // Remove previously generated menus.
+    for entry in fs::read_dir(dir)?.flatten() {
+        if entry.file_name().to_string_lossy().starts_with("tooly-") {
+            fs::remove_file(entry.path()).ok();
+        }
+    }
+    let globs = mime_globs();
+    let mut menus: Vec<((String, String), MenuEntries)> = Vec::new();
+    for (id, item) in enabled_items(settings) {
+        let key = (item.group.clone(), item.target_type.clone());
+        match menus.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, entries)) => entries.push((id, item)),
+            None => menus.push((key, vec![(id, item)])),
+        }
+    }
+    for (index, ((group, target_type), entries)) in menus.iter().enumerate() {
+        let submenu = if group.is_empty() { MENU_FOLDER } else { group };
+        let actions: Vec<String> = (0..entries.len()).map(|i| format!("tooly{i}")).collect();
+        let mut content = format!(
+            "[Desktop Entry]\n\
+            Type=Service\n\
+            MimeType={mime}\n\
+            Actions={actions};\n\
+            X-KDE-Submenu={submenu}\n",
+            mime = dolphin_mime_types(target_type, &globs),
+            actions = actions.join(";"),
+            submenu = desktop_escape(submenu),
+        );
+        for ((id, item), action) in entries.iter().zip(&actions) {
+            content.push_str(&format!(
+                "\n[Desktop Action {action}]\n\
+                Name={name}\n\
+                Icon={icon}\n\
+                Exec={script} %F\n",
+                name = desktop_escape(id),
+                icon = desktop_escape(&linux_icon(item)),
+                script = desktop_escape(&exec_quote(&scripts_dir.join(script_name(id)))),
+            ));
+        }
+        // Dolphin only loads service menus that are executable.
+        write_executable(&dir.join(format!("tooly-{index}.desktop")), &content)?;
+    }
+    Ok(())
+}
+
+/// Replace Tooly actions in Thunar's `uca.xml`, keeping the user's own.
+fn write_thunar_actions(
+    settings: &MenuSettings,
+    scripts_dir: &Path,
+    path: &Path,
+) -> std::io::Result<()> {
+    let Some(dir) = path.parent() else {
+        return Ok(());
+    };
+    if !dir.exists() {
+        return Ok(());
+    }
+    let existing = fs::read_to_string(path).unwrap_or_else(|_| {
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<actions>\n</actions>\n".to_string()
+    });
+    let mut content = remove_thunar_actions(&existing);
+    let mut actions = String::new();
+    for (id, item) in enabled_items(settings) {
+        let submenu = if item.group.is_empty() {
+            MENU_FOLDER.to_string()
+        } else {
+            format!("{MENU_FOLDER}/{}", item.group)
+        };
+        actions.push_str(&format!(
+            "<action>\n\
+            \t<icon>{icon}</icon>\n\
+            \t<name>{name}</name>\n\
+            \t<submenu>{submenu}</submenu>\n\
+            \t<unique-id>{prefix}{slug}</unique-id>\n\
+            \t<command>{script} %F</command>\n\
+            \t<description>{name}</description>\n\
+            {conditions}\
+            </action>\n",
+            icon = xml_escape(&linux_icon(item)),
+            name = xml_escape(id),
+            submenu = xml_escape(&submenu),
+            prefix = THUNAR_ID_PREFIX,
+            slug = script_name(id),
+            script = xml_escape(&command_quote(&scripts_dir.join(script_name(id)))),
+            conditions = thunar_conditions(&item.target_type),
+        ));
+    }
+    match content.rfind("</actions>") {
+        Some(end) => content.insert_str(end, &actions),
+        None => content.push_str(&format!("<actions>\n{actions}</actions>\n")),
+    }
+    fs::write(path, content)
+}
+
+// endregion
+
+// region: Utils
+
+/// Return enabled items in menu order.
+fn enabled_items(settings: &MenuSettings) -> MenuEntries<'_> {
+    settings
+        .order
+        .iter()
+        .filter_map(|id| settings.items.get_key_value(id))
+        .filter(|(_, item)| item.enabled)
+        .collect()
+}
+
+/// Build the launcher script for an item. It turns the selection into
+/// absolute paths and runs `tooly --run <id> <paths>`, which the running
+/// instance handles (see `utils::execute_args`).
+fn item_script(id: &str, exe: &Path) -> String {
+    format!(
+        r#"#!/bin/sh
+# Generated by Tooly from settings.json, changes will be overwritten.
+[ $# -eq 0 ] && set -- "$PWD"
+for f do
+    shift
+    case "$f" in /*) ;; *) f="$PWD/$f" ;; esac
+    set -- "$@" "$f"
+done
+exec {exe} --run {id} "$@"
+"#,
+        exe = shell_quote(&exe.to_string_lossy()),
+        id = shell_quote(id),
+    )
+}
+
+/// Path of the Tooly executable, the AppImage itself when running from one.
+fn executable() -> Option<PathBuf> {
+    env::var_os("APPIMAGE")
+        .map(PathBuf::from)
+        .or_else(|| env::current_exe().ok())
+}
+
+/// Dolphin mime types for a target type. Extensions are mapped to the types
+/// registered for them in the shared MIME database.
+fn dolphin_mime_types(target_type: &str, globs: &MimeGlobs) -> String {
+    match target_type {
+        "folder" => "inode/directory;".to_string(),
+        "file" => "all/allfiles;".to_string(),
+        "any" => "all/all;".to_string(),
+        extensions => {
+            let mut types: Vec<&str> = Vec::new();
+            for extension in extensions.split(',').map(|e| e.trim().to_lowercase()) {
+                if extension.is_empty() {
+                    continue;
+                }
+                let glob = format!("*.{extension}");
+                let mut known: Vec<&str> = globs
+                    .iter()
+                    .filter(|(pattern, _)| *pattern == glob)
+                    .map(|(_, mime)| mime.as_str())
+                    .collect();
+                if known.is_empty() {
+                    known.push(UNKNOWN_MIME_TYPE);
+                }
+                for mime in known {
+                    if !types.contains(&mime) {
+                        types.push(mime);
+                    }
+                }
+            }
+            types.iter().map(|mime| format!("{mime};")).collect()
+        }
+    }
+}
+
+/// Read the glob patterns of the shared MIME database (`mime/globs2` in the
+/// XDG data folders).
+fn mime_globs() -> MimeGlobs {
+    let data_home = env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".local/share")));
+    let data_dirs = env::var_os("XDG_DATA_DIRS")
+        .filter(|dirs| !dirs.is_empty())
+        .unwrap_or_else(|| "/usr/local/share:/usr/share".into());
+    data_home
+        .into_iter()
+        .chain(env::split_paths(&data_dirs))
+        .filter_map(|dir| fs::read_to_string(dir.join("mime/globs2")).ok())
+        .flat_map(|content| {
+            // Lines are `weight:type:glob[:flags]`.
+            content
+                .lines()
+                .filter(|line| !line.starts_with('#'))
+                .filter_map(|line| {
+                    let mut fields = line.split(':').skip(1);
+                    let mime = fields.next()?;
+                    let glob = fields.next()?;
+                    Some((glob.to_lowercase(), mime.to_string()))
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// Thunar file type conditions for a target type.
+fn thunar_conditions(target_type: &str) -> String {
+    const FILES: &str =
+        "\t<audio-files/>\n\t<image-files/>\n\t<other-files/>\n\t<text-files/>\n\t<video-files/>\n";
+    match target_type {
+        "any" => format!("\t<patterns>*</patterns>\n\t<directories/>\n{FILES}"),
+        "folder" => "\t<patterns>*</patterns>\n\t<directories/>\n".to_string(),
+        "file" => format!("\t<patterns>*</patterns>\n{FILES}"),
+        extensions => {
+            let patterns: Vec<String> = extensions
+                .split(',')
+                .flat_map(|ext| {
+                    [
+                        format!("*.{}", ext.to_lowercase()),
+                        format!("*.{}", ext.to_uppercase()),
+                    ]
+                })
+                .collect();
+            format!(
+                "\t<patterns>{}</patterns>\n{FILES}",
+                xml_escape(&patterns.join(";"))
+            )
+        }
+    }
+}
+
+/// Remove previously generated `<action>` blocks from `uca.xml` content.
+fn remove_thunar_actions(xml: &str) -> String {
+    let mut result = String::with_capacity(xml.len());
+    let mut rest = xml;
+    while let Some(start) = rest.find("<action>") {
+        let Some(length) = rest[start..].find("</action>") else {
+            break;
+        };
+        let mut end = start + length + "</action>".len();
+        if rest[end..].starts_with('\n') {
+            end += 1;
+        }
+        let block = &rest[start..end];
+        result.push_str(&rest[..start]);
+        if !block.contains(&format!("<unique-id>{THUNAR_ID_PREFIX}")) {
+            result.push_str(block);
+        }
+        rest = &rest[end..];
+    }
+    result.push_str(rest);
+    result
+}
+
+/// Use themed icon names only; macOS symbols and app paths do not apply.
+fn linux_icon(item: &MenuItem) -> String {
+    match item.icon_type.as_str() {
+        "image" => item.icon.clone(),
+        _ => "tooly".to_string(),
+    }
+}
+
+/// Turn an id into a safe file name.
+fn file_name(id: &str) -> String {
+    id.replace(['/', '\0'], "-")
+}
+
+/// Turn an id into a launcher script name made of `[A-Za-z0-9_-]` only, so
+/// it needs no escaping in Dolphin and Thunar commands. A hash of the id
+/// keeps names unique when ids only differ in replaced characters.
+fn script_name(id: &str) -> String {
+    let slug: String = id
+        .chars()
+        .map(|c| match c {
+            'A'..='Z' | 'a'..='z' | '0'..='9' | '_' | '-' => c,
+            _ => '_',
+        })
+        .collect();
+    // FNV-1a, stable across builds unlike the std hasher.
+    let hash = id.bytes().fold(0x811c9dc5u32, |hash, byte| {
+        (hash ^ byte as u32).wrapping_mul(0x01000193)
+    });
+    format!("{slug}-{hash:08x}")
+}
+
+/// Quote a path for a `.desktop` `Exec` key: reserved characters are
+/// escaped inside double quotes and `%` is doubled so it is not taken as a
+/// field code. String level escaping is left to `desktop_escape`.
+fn exec_quote(path: &Path) -> String {
+    let mut quoted = String::from("\"");
+    for c in path.to_string_lossy().chars() {
+        match c {
+            '"' | '`' | '$' | '\\' => {
+                quoted.push('\\');
+                quoted.push(c);
+            }
+            '%' => quoted.push_str("%%"),
+            _ => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Quote a path for a Thunar custom action command, which is split like a
+/// shell command line after `%` field codes are expanded.
+fn command_quote(path: &Path) -> String {
+    shell_quote(&path.to_string_lossy()).replace('%', "%%")
+}
+
+/// Remove and recreate a folder owned by Tooly.
+fn recreate_dir(dir: &Path) -> std::io::Result<()> {
+    if dir.exists() {
+        fs::remove_dir_all(dir)?;
+    }
+    fs::create_dir_all(dir)
+}
+
+/// Write a file and make it executable.
+fn write_executable(path: &Path, content: &str) -> std::io::Result<()> {
+    fs::write(path, content)?;
+    fs::set_permissions(path, fs::Permissions::from_mode(0o755))
+}
+
+/// Quote a value for POSIX shells.
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+/// Escape a value for `.desktop` files.
+fn desktop_escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('\n', "\\n")
+        .replace(';', "\\;")
+}
+
+/// Escape a value for XML text.
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// endregion
@@ -0,0 +1,602 @@
+use crate::color::{self, Color, ColorFormat, ColorValues};
+use serde::{Deserialize, Serialize};
+use std::process::Command;
+use std::sync::Mutex;
+use std::time::Duration;
+use std::{fs, path::Path, path::PathBuf, thread};
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+
+// region: Variables
+
+/// Number of recent colors kept.
+const RECENT_LIMIT: usize = 24;
+
+/// Seconds to wait for the portal color picker (Wayland).
+#[cfg(target_os = "linux")]
+const PORTAL_TIMEOUT: u64 = 120;
+
+/// Serializes palette reads and writes across threads.
+static STORE_LOCK: Mutex<()> = Mutex::new(());
+
+/// Session bus connection kept for portal requests (Wayland).
+#[cfg(target_os = "linux")]
+static SESSION_BUS: once_cell::sync::OnceCell<zbus::Connection> = once_cell::sync::OnceCell::new();
+
+// endregion
+
+// region: Structs
+
+/// Saved palettes and recently used colors, stored in `palettes.json`.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct Store {
+    pub palettes: Vec<Palette>,
+    /// Hex colors, most recent first.
+    pub recent: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Palette {
+    pub name: String,
+    #[serde(default)]
+    pub colors: Vec<Swatch>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Swatch {
+    /// Optional, used for variable names in exports.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub name: String,
+    /// Hex color (`#rrggbb` or `#rrggbbaa`).
+    pub color: String,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    /// CSS custom properties on `:root`.
+    Css,
+    /// `tailwind.config.js` extending the theme colors.
+    Tailwind,
+    /// Adobe Swatch Exchange.
+    Ase,
+    /// GIMP palette.
+    Gpl,
+}
+
+// endregion
+
+// region: Commands
+
+/// Return the saved palettes and recent colors.
+#[tauri::command]
+pub fn get_palettes(app: AppHandle) -> Store {
+    let _lock = STORE_LOCK.lock().unwrap();
+    load(&app)
+}
+
+/// Add or replace a palette (by name). Colors may be written in any format
+/// and are stored as hex.
+#[tauri::command]
+pub fn save_palette(app: AppHandle, mut palette: Palette) -> Result<Palette, String> {
+    palette.name = palette.name.trim().to_string();
+    if palette.name.is_empty() {
+        return Err("Palette name cannot be empty.".to_string());
+    }
+    for swatch in palette.colors.iter_mut() {
+        swatch.color = color::format(&color::parse(&swatch.color)?, ColorFormat::Hex);
+    }
+    update(&app, |store| {
+        match store.palettes.iter_mut().find(|p| p.name == palette.name) {
+            Some(existing) => *existing = palette.clone(),
+            None => store.palettes.push(palette.clone()),
+        }
+    })?;
+    println!("Palette - Saved palette '{}'.", palette.name);
+    Ok(palette)
+}
+
+/// Delete a palette.
+#[tauri::command]
+pub fn remove_palette(app: AppHandle, name: String) -> Result<(), String> {
+    let mut found = false;
+    update(&app, |store| {
+        let count = store.palettes.len();
+        store.palettes.retain(|p| p.name != name);
+        found = store.palettes.len() != count;
+    })?;
+    if !found {
+        return Err(format!("Palette '{name}' does not exist."));
+    }
+    println!("Palette - Removed palette '{name}'.");
+    Ok(())
+}
+
+/// Add a color to the recent colors.
+#[tauri::command]
+pub fn add_recent_color(app: AppHandle, color: String) -> Result<Vec<String>, String> {
+    let color = color::parse(&color)?;
+    remember(&app, &color)
+}
+
+/// Forget the recent colors.
+#[tauri::command]
+pub fn clear_recent_colors(app: AppHandle) -> Result<(), String> {
+    update(&app, |store| store.recent.clear())
+}
+
+/// Write a color in every format.
+#[tauri::command]
+pub fn convert_color(color: String) -> Result<ColorValues, String> {
+    color::parse(&color).map(|color| color::values(&color))
+}
+
+/// Copy a color to the clipboard in a format, and add it to the recent
+/// colors. Returns the copied text.
+#[tauri::command]
+pub fn copy_color(app: AppHandle, color: String, format: ColorFormat) -> Result<String, String> {
+    let color = color::parse(&color)?;
+    let text = color::format(&color, format);
+    app.clipboard()
+        .write_text(text.clone())
+        .map_err(|e| e.to_string())?;
+    remember(&app, &color)?;
+    Ok(text)
+}
+
+/// Sample the screen color under the cursor after `delay` milliseconds,
+/// so the cursor can be moved off Tooly first. Wayland shows the portal
+/// picker instead. Emits `color-picked` with the color, which is also
+/// added to the recent colors, or `color-pick-failed` with the reason.
+#[tauri::command]
+pub fn pick_color(app: AppHandle, delay: Option<u64>) {
+    thread::spawn(move || {
+        thread::sleep(Duration::from_millis(delay.unwrap_or(0)));
+        let picked = sample(&app).and_then(|color| {
+            remember(&app, &color)?;
+            Ok(color::values(&color))
+        });
+        match picked {
+            Ok(values) => {
+                println!("Palette - Picked {}.", values.hex);
+                app.emit("color-picked", values).ok();
+            }
+            Err(e) => {
+                eprintln!("Palette - Failed to pick color: {e}");
+                app.emit("color-pick-failed", e).ok();
+            }
+        }
+    });
+}
+
+/// Write a palette to a file as CSS variables, a Tailwind config, an ASE or
+/// a GPL file.
+#[tauri::command]
+pub fn export_palette(
+    app: AppHandle,
+    name: String,
+    format: ExportFormat,
+    path: String,
+) -> Result<(), String> {
+    let palette = {
+        let _lock = STORE_LOCK.lock().unwrap();
+        load(&app).palettes.into_iter().find(|p| p.name == name)
+    }
+    .ok_or(format!("Palette '{name}' does not exist."))?;
+    let path = Path::new(&path);
+    let data = match format {
+        ExportFormat::Css => export_css(&palette)?.into_bytes(),
+        ExportFormat::Tailwind => export_tailwind(&palette)?.into_bytes(),
+        ExportFormat::Ase => export_ase(&palette)?,
+        ExportFormat::Gpl => export_gpl(&palette)?.into_bytes(),
+    };
+    fs::write(path, data).map_err(|e| format!("Failed to write '{}': {e}", path.display()))?;
+    println!(
+        "Palette - Exported palette '{name}' to '{}'.",
+        path.display()
+    );
+    Ok(())
+}
+
+// endregion
+
+// region: Store
+
+fn store_path(app: &AppHandle) -> PathBuf {
+    app.path().app_data_dir().unwrap().join("palettes.json")
+}
+
+/// Load the store, starting fresh if it is missing or unreadable.
+fn load(app: &AppHandle) -> Store {
+    fs::read_to_string(store_path(app))
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+/// Change the store and write it through a temporary file.
+fn update(app: &AppHandle, f: impl FnOnce(&mut Store)) -> Result<(), String> {
+    let _lock = STORE_LOCK.lock().unwrap();
+    let mut store = load(app);
+    f(&mut store);
+    let path = store_path(app);
+    let write = || -> std::io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let temp = path.with_extension("json.tmp");
+        fs::write(&temp, serde_json::to_string_pretty(&store)?)?;
+        fs::rename(&temp, &path)
+    };
+    write().map_err(|e| format!("Failed to save palettes: {e}"))
+}
+
+/// Move a color to the top of the recent colors.
+fn remember(app: &AppHandle, color: &Color) -> Result<Vec<String>, String> {
+    let hex = color::format(color, ColorFormat::Hex);
+    let mut recent = Vec::new();
+    update(app, |store| {
+        store.recent.retain(|c| *c != hex);
+        store.recent.insert(0, hex.clone());
+        store.recent.truncate(RECENT_LIMIT);
+        recent = store.recent.clone();
+    })?;
+    Ok(recent)
+}
+
+// endregion
+
+// region: Sampling
+
+/// Read the color of the screen pixel under the cursor.
+#[cfg(target_os = "windows")]
+fn sample(_app: &AppHandle) -> Result<Color, String> {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+    let output = Command::new("powershell")
+        .args([
+            "-NoProfile",
+            "-Command",
+            "Add-Type -AssemblyName System.Windows.Forms; \
+            $g = Add-Type -Name G -PassThru -MemberDefinition \
+            '[DllImport(\"user32.dll\")] public static extern IntPtr GetDC(IntPtr h); \
+            [DllImport(\"user32.dll\")] public static extern int ReleaseDC(IntPtr h, IntPtr dc); \
+            [DllImport(\"gdi32.dll\")] public static extern uint GetPixel(IntPtr dc, int x, int y);'; \
+            $p = [System.Windows.Forms.Cursor]::Position; \
+            $dc = $g::GetDC([IntPtr]::Zero); \
+            $g::GetPixel($dc, $p.X, $p.Y); \
+            [void]$g::ReleaseDC([IntPtr]::Zero, $dc)",
+        ])
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()
+        .map_err(|e| format!("Failed to run powershell: {e}"))?;
+    // COLORREF values are 0x00bbggrr.
+    let value: u32 = String::from_utf8_lossy(&output.stdout)
+        .trim()
+        .parse()
+        .map_err(|_| "Failed to read the screen color.".to_string())?;
+    let [r, g, b, _] = value.to_le_bytes();
+    Ok(Color::from_rgb8(r, g, b))
+}
+
+/// Read the color of the screen pixel under the cursor. Requires the
+/// screen recording permission.
+#[cfg(target_os = "macos")]
+fn sample(app: &AppHandle) -> Result<Color, String> {
+    let position = app.cursor_position().map_err(|e| e.to_string())?;
+    // `screencapture` works in points, the cursor position is in pixels.
+    let scale = app
+        .monitor_from_point(position.x, position.y)
+        .ok()
+        .flatten()
+        .map(|m| m.scale_factor())
+        .unwrap_or(1.0);
+    let (x, y) = (position.x / scale, position.y / scale);
+    let path = std::env::temp_dir().join(format!("tooly-color-{}.png", std::process::id()));
+    let status = Command::new("screencapture")
+        .args(["-x", "-t", "png"])
+        .arg(format!("-R{},{},1,1", x.floor(), y.floor()))
+        .arg(&path)
+        .status()
+        .map_err(|e| format!("Failed to run screencapture: {e}"))?;
+    let png = fs::read(&path);
+    fs::remove_file(&path).ok();
+    match png {
+        Ok(png) if status.success() => first_pixel(&png),
+        _ => Err("Failed to capture the screen.".to_string()),
+    }
+}
+
+/// Read the color of the screen pixel under the cursor with ImageMagick on
+/// X11, or ask the desktop portal to pick one on Wayland.
+#[cfg(target_os = "linux")]
+fn sample(app: &AppHandle) -> Result<Color, String> {
+    if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        return sample_portal();
+    }
+    let position = app.cursor_position().map_err(|e| e.to_string())?;
+    let crop = format!("1x1+{}+{}", position.x.floor(), position.y.floor());
+    let output = Command::new("import")
+        .args(["-silent", "-window", "root", "-crop", &crop, "-depth", "8"])
+        .arg("txt:-")
+        .output()
+        .map_err(|e| format!("Failed to run ImageMagick 'import': {e}"))?;
+    // Output looks like `0,0: (255,136,0)  #FF8800  srgb(255,136,0)`.
+    let text = String::from_utf8_lossy(&output.stdout);
+    text.split_whitespace()
+        .find(|word| word.starts_with('#') && word.len() >= 7)
+        .and_then(|hex| color::parse(&hex[..7]).ok())
+        .ok_or("Failed to read the screen color.".to_string())
+}
+
+/// Pick a color with the desktop portal (`Screenshot.PickColor`). The answer
+/// comes as a `Response` signal on the request object, whose path is known
+/// from the connection name and a new handle token, so it is watched before
+/// the call is made.
+#[cfg(target_os = "linux")]
+fn sample_portal() -> Result<Color, String> {
+    use futures_lite::{future, StreamExt};
+    use std::collections::HashMap;
+    use zbus::zvariant::{ObjectPath, OwnedObjectPath, OwnedValue, Value};
+    use zbus::{Connection, Proxy};
+
+    const PORTAL: &str = "org.freedesktop.portal.Desktop";
+    let failed = |e: zbus::Error| format!("Portal request failed: {e}");
+    let connection = SESSION_BUS
+        .get_or_try_init(|| zbus::block_on(Connection::session()))
+        .map_err(failed)?;
+    let mut bytes = [0u8; 8];
+    getrandom::getrandom(&mut bytes).map_err(|e| e.to_string())?;
+    let token = format!("tooly_{}", hex::encode(bytes));
+    let sender = connection
+        .unique_name()
+        .ok_or("Not connected to the session bus.")?
+        .trim_start_matches(':')
+        .replace('.', "_");
+    let path = format!("/org/freedesktop/portal/desktop/request/{sender}/{token}");
+    zbus::block_on(async {
+        let request = |path: ObjectPath<'static>| {
+            Proxy::new(connection, PORTAL, path, "org.freedesktop.portal.Request")
+        };
+        let expected = ObjectPath::try_from(path).map_err(|e| failed(e.into()))?;
+        let mut responses = request(expected.clone())
+            .await
+            .map_err(failed)?
+            .receive_signal("Response")
+            .await
+            .map_err(failed)?;
+        let screenshot = Proxy::new(
+            connection,
+            PORTAL,
+            "/org/freedesktop/portal/desktop",
+            "org.freedesktop.portal.Screenshot",
+        )
+        .await
+        .map_err(failed)?;
+        let options = HashMap::from([("handle_token", Value::from(token.as_str()))]);
+        let handle: OwnedObjectPath = screenshot
+            .call("PickColor", &("", options))
+            .await
+            .map_err(failed)?;
+        // Older portals use another request path.
+        if *handle != expected {
+            responses = request(handle.into_inner())
+                .await
+                .map_err(failed)?
+                .receive_signal("Response")
+                .await
+                .map_err(failed)?;
+        }
+        let timeout = async {
+            async_io::Timer::after(Duration::from_secs(PORTAL_TIMEOUT)).await;
+            None
+        };
+        let message = future::or(responses.next(), timeout)
+            .await
+            .ok_or("The color picker did not answer.")?;
+        // A non-zero code means the pick was cancelled.
+        let (code, results): (u32, HashMap<String, OwnedValue>) =
+            message.body().deserialize().map_err(failed)?;
+        if code != 0 {
+            return Err("Color picking was cancelled.".to_string());
+        }
+        let color = results
+            .get("color")
+            .ok_or("Invalid portal response.")?
+            .try_clone()
+            .map_err(|e| failed(e.into()))?;
+        let (r, g, b): (f64, f64, f64) = Value::from(color)
+            .try_into()
+            .map_err(|_| "Invalid portal response.".to_string())?;
+        Ok(Color { r, g, b, a: 1.0 })
+    })
+}
+
+// endregion
+
+// region: Exports
+
+fn export_css(palette: &Palette) -> Result<String, String> {
+    let prefix = palette_slug(palette);
+    let mut css = String::from(":root {\n");
+    for (index, swatch) in palette.colors.iter().enumerate() {
+        let hex = hex_color(swatch)?;
+        css.push_str(&format!(
+            "  --{prefix}-{}: {hex};\n",
+            swatch_name(swatch, index)
+        ));
+    }
+    css.push_str("}\n");
+    Ok(css)
+}
+
+fn export_tailwind(palette: &Palette) -> Result<String, String> {
+    let mut colors = String::new();
+    for (index, swatch) in palette.colors.iter().enumerate() {
+        let hex = hex_color(swatch)?;
+        colors.push_str(&format!(
+            "          '{}': '{hex}',\n",
+            swatch_name(swatch, index)
+        ));
+    }
+    Ok(format!(
+        "/** @type {{import('tailwindcss').Config}} */\n\
+        module.exports = {{\n  theme: {{\n    extend: {{\n      colors: {{\n        \
+        '{}': {{\n{colors}        }},\n      }},\n    }},\n  }},\n}};\n",
+        palette_slug(palette)
+    ))
+}
+
+/// GIMP palette, alpha is dropped.
+fn export_gpl(palette: &Palette) -> Result<String, String> {
+    let mut gpl = format!("GIMP Palette\nName: {}\nColumns: 0\n#\n", palette.name);
+    for (index, swatch) in palette.colors.iter().enumerate() {
+        let [r, g, b] = color::parse(&swatch.color)?.rgb8();
+        let name = match swatch.name.as_str() {
+            "" => format!("Color {}", index + 1),
+            name => name.to_string(),
+        };
+        gpl.push_str(&format!("{r:3} {g:3} {b:3}\t{name}\n"));
+    }
+    Ok(gpl)
+}
+
+/// Adobe Swatch Exchange (version 1.0): one group named after the palette
+/// holding RGB colors. Values are big-endian, names are UTF-16 with a
+/// terminating null.
+fn export_ase(palette: &Palette) -> Result<Vec<u8>, String> {
+    let block = |data: &mut Vec<u8>, kind: u16, body: &[u8]| {
+        data.extend(kind.to_be_bytes());
+        data.extend((body.len() as u32).to_be_bytes());
+        data.extend(body);
+    };
+    let name = |value: &str| {
+        let units: Vec<u16> = value.encode_utf16().chain([0]).collect();
+        let mut bytes = (units.len() as u16).to_be_bytes().to_vec();
+        bytes.extend(units.iter().flat_map(|u| u.to_be_bytes()));
+        bytes
+    };
+    let mut data = b"ASEF".to_vec();
+    data.extend(1u16.to_be_bytes());
+    data.extend(0u16.to_be_bytes());
+    data.extend((palette.colors.len() as u32 + 2).to_be_bytes());
+    block(&mut data, 0xC001, &name(&palette.name));
+    for swatch in &palette.colors {
+        let color = color::parse(&swatch.color)?;
+        let mut body = name(&match swatch.name.as_str() {
+            "" => color::format(&color, ColorFormat::Hex),
+            name => name.to_string(),
+        });
+        body.extend(b"RGB ");
+        for channel in [color.r, color.g, color.b] {
+            body.extend((channel as f32).to_be_bytes());
+        }
+        // Global, spot or normal color.
+        body.extend(2u16.to_be_bytes());
+        block(&mut data, 0x0001, &body);
+    }
+    block(&mut data, 0xC002, &[]);
+    Ok(data)
+}
+
+// endregion
+
+// region: Utils
+
+/// Read the first pixel of a PNG image.
+#[cfg(target_os = "macos")]
+fn first_pixel(png: &[u8]) -> Result<Color, String> {
+    let failed = |e: png::DecodingError| format!("Failed to decode capture: {e}");
+    let mut decoder = png::Decoder::new(png);
+    decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+    let mut reader = decoder.read_info().map_err(failed)?;
+    let mut pixels = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut pixels).map_err(failed)?;
+    match (info.color_type, &pixels[..]) {
+        (png::ColorType::Rgb | png::ColorType::Rgba, [r, g, b, ..]) => {
+            Ok(Color::from_rgb8(*r, *g, *b))
+        }
+        (png::ColorType::Grayscale | png::ColorType::GrayscaleAlpha, [v, ..]) => {
+            Ok(Color::from_rgb8(*v, *v, *v))
+        }
+        _ => Err("Unsupported capture format.".to_string()),
+    }
+}
+
+/// Swatch color as hex, checked.
+fn hex_color(swatch: &Swatch) -> Result<String, String> {
+    color::parse(&swatch.color).map(|color| color::format(&color, ColorFormat::Hex))
+}
+
+/// Palette name for variables.
+fn palette_slug(palette: &Palette) -> String {
+    match slug(&palette.name) {
+        name if name.is_empty() => "palette".to_string(),
+        name => name,
+    }
+}
+
+/// Swatch name for variables, its position when unnamed.
+fn swatch_name(swatch: &Swatch, index: usize) -> String {
+    match slug(&swatch.name) {
+        name if name.is_empty() => (index + 1).to_string(),
+        name => name,
+    }
+}
+
+/// Lower case words joined with dashes (`Brand Blue` gives `brand-blue`).
+fn slug(value: &str) -> String {
+    value
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+// endregion
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exports_ase() {
+        let palette = Palette {
+            name: "Brand".to_string(),
+            colors: vec![
+                Swatch {
+                    name: String::new(),
+                    color: "#ff8800".to_string(),
+                },
+                Swatch {
+                    name: "Ink".to_string(),
+                    color: "#000000".to_string(),
+                },
+            ],
+        };
+        #[rustfmt::skip]
+        let expected: Vec<u8> = [
+            // Signature, version 1.0 and 4 blocks.
+            &b"ASEF"[..], &[0, 1, 0, 0], &[0, 0, 0, 4],
+            // Group start, "Brand" with its null terminator.
+            &[0xC0, 0x01], &[0, 0, 0, 14], &[0, 6],
+            &[0, b'B', 0, b'r', 0, b'a', 0, b'n', 0, b'd', 0, 0],
+            // Unnamed color, named after its hex value.
+            &[0x00, 0x01], &[0, 0, 0, 36], &[0, 8],
+            &[0, b'#', 0, b'f', 0, b'f', 0, b'8', 0, b'8', 0, b'0', 0, b'0', 0, 0],
+            b"RGB ", &[0x3F, 0x80, 0, 0], &[0x3F, 0x08, 0x88, 0x89], &[0, 0, 0, 0],
+            &[0, 2],
+            // Named color.
+            &[0x00, 0x01], &[0, 0, 0, 28], &[0, 4],
+            &[0, b'I', 0, b'n', 0, b'k', 0, 0],
+            b"RGB ", &[0, 0, 0, 0], &[0, 0, 0, 0], &[0, 0, 0, 0],
+            &[0, 2],
+            // Group end.
+            &[0xC0, 0x02], &[0, 0, 0, 0],
+        ]
+        .concat();
+        assert_eq!(export_ase(&palette).unwrap(), expected);
+    }
+}
@@ -0,0 +1,182 @@
+use crate::settings;
+use crate::utils::Payload;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::{fs, io::Write, path::Path, path::PathBuf};
+use tauri::{AppHandle, Url};
+
+// region: Variables
+
+/// Shared secret used by the Finder extension to sign urls.
+const KEY_FILE: &str = "secret.key";
+
+/// Folder holding payloads too large to be sent inline.
+const TOKEN_FOLDER: &str = "tokens";
+
+/// Token payloads older than this are rejected and removed, inline payloads
+/// issued further away from now are rejected.
+const MAX_AGE: Duration = Duration::from_secs(60);
+
+/// Nonces of recent inline payloads with their issue time, so each runs once.
+static SEEN_NONCES: Mutex<Option<HashMap<String, u64>>> = Mutex::new(None);
+
+// endregion
+
+// region: Payloads
+
+/// Decode and verify the payload of a `tooly://run` url.
+///
+/// The payload is either carried inline (`payload=<base64url json>`) or
+/// stored by the extension under a one-time token (`token=<id>`). `sig` must
+/// be the hex HMAC-SHA256, keyed with the contents of `secret.key`, of the
+/// token, or of `<ts>:<nonce>:<payload>` for inline payloads, where `ts` is
+/// the issue time in seconds since the epoch and `nonce` a random id.
+pub fn decode(app: &AppHandle, url: &Url) -> Result<Payload, String> {
+    let param = |name: &str| {
+        url.query_pairs()
+            .find(|(k, _)| k == name)
+            .map(|(_, v)| v.to_string())
+    };
+    let Some(signature) = param("sig") else {
+        return Err("Unsigned request.".to_string());
+    };
+    let json = if let Some(inline) = param("payload") {
+        let (Some(issued), Some(nonce)) = (param("ts"), param("nonce")) else {
+            return Err("Missing 'ts' or 'nonce' parameter.".to_string());
+        };
+        verify(app, &format!("{issued}:{nonce}:{inline}"), &signature)?;
+        check_fresh(&issued, &nonce)?;
+        let bytes = URL_SAFE_NO_PAD
+            .decode(inline.trim_end_matches('='))
+            .map_err(|e| format!("Invalid payload encoding: {e}"))?;
+        String::from_utf8(bytes).map_err(|e| format!("Invalid payload encoding: {e}"))?
+    } else if let Some(token) = param("token") {
+        verify(app, &token, &signature)?;
+        take_token(app, &token)?
+    } else {
+        return Err("Missing 'payload' or 'token' parameter.".to_string());
+    };
+    serde_json::from_str(&json).map_err(|e| format!("Invalid payload: {e}"))
+}
+
+/// Create the signing key on first launch and remove expired token files.
+pub fn ensure_key(app: &AppHandle) {
+    let path = key_path(app);
+    if !path.exists() {
+        match create_key(&path) {
+            Ok(_) => println!("Payload - Created signing key."),
+            Err(e) => eprintln!("Payload - Failed to create signing key: {e}"),
+        }
+    }
+    let Ok(entries) = fs::read_dir(settings::settings_dir(app).join(TOKEN_FOLDER)) else {
+        return;
+    };
+    // Fresh tokens may belong to the url that launched the app.
+    for entry in entries.flatten() {
+        let expired = entry
+            .metadata()
+            .and_then(|m| m.modified())
+            .map(|t| t.elapsed().unwrap_or_default() > MAX_AGE)
+            .unwrap_or(true);
+        if expired {
+            fs::remove_file(entry.path()).ok();
+        }
+    }
+}
+
+/// Check a signature against the shared key in constant time.
+fn verify(app: &AppHandle, message: &str, signature: &str) -> Result<(), String> {
+    let key = fs::read_to_string(key_path(app))
+        .map_err(|e| format!("Failed to read signing key: {e}"))?;
+    let signature = hex::decode(signature).map_err(|_| "Malformed signature.".to_string())?;
+    let mut mac = Hmac::<Sha256>::new_from_slice(key.trim().as_bytes())
+        .map_err(|e| format!("Invalid signing key: {e}"))?;
+    mac.update(message.as_bytes());
+    mac.verify_slice(&signature)
+        .map_err(|_| "Invalid signature.".to_string())
+}
+
+/// Reject inline payloads issued too long ago (or ahead of now) and nonces
+/// already used, so a captured url cannot be replayed.
+fn check_fresh(issued: &str, nonce: &str) -> Result<(), String> {
+    let issued: u64 = issued
+        .parse()
+        .map_err(|_| "Malformed timestamp.".to_string())?;
+    if !is_id(nonce) {
+        return Err("Malformed nonce.".to_string());
+    }
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let fresh = |time: u64| now.abs_diff(time) <= MAX_AGE.as_secs();
+    if !fresh(issued) {
+        return Err("Expired request.".to_string());
+    }
+    let mut seen = SEEN_NONCES.lock().unwrap();
+    let seen = seen.get_or_insert_with(HashMap::new);
+    seen.retain(|_, time| fresh(*time));
+    if seen.insert(nonce.to_string(), issued).is_some() {
+        return Err("Request already handled.".to_string());
+    }
+    Ok(())
+}
+
+/// Read and delete the payload stored under a token, so it runs only once.
+fn take_token(app: &AppHandle, token: &str) -> Result<String, String> {
+    if !is_id(token) {
+        return Err("Malformed token.".to_string());
+    }
+    let path = settings::settings_dir(app)
+        .join(TOKEN_FOLDER)
+        .join(format!("{token}.json"));
+    let age = fs::metadata(&path)
+        .and_then(|m| m.modified())
+        .map_err(|_| "Unknown or used token.".to_string())?
+        .elapsed()
+        .unwrap_or_default();
+    let json = fs::read_to_string(&path);
+    fs::remove_file(&path).ok();
+    if age > MAX_AGE {
+        return Err("Expired token.".to_string());
+    }
+    json.map_err(|e| format!("Failed to read token: {e}"))
+}
+
+// endregion
+
+// region: Utils
+
+/// Return the signing key path, also read by the Finder extension.
+pub fn key_path(app: &AppHandle) -> PathBuf {
+    settings::settings_dir(app).join(KEY_FILE)
+}
+
+/// Check that a token or nonce looks like a hex id or a UUID.
+fn is_id(value: &str) -> bool {
+    (16..=64).contains(&value.len()) && value.chars().all(|c| c.is_ascii_hexdigit() || c == '-')
+}
+
+/// Write a new random key, readable by the current user only.
+fn create_key(path: &Path) -> std::io::Result<()> {
+    let mut bytes = [0u8; 32];
+    getrandom::getrandom(&mut bytes).map_err(|e| std::io::Error::other(e.to_string()))?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options
+        .open(path)?
+        .write_all(hex::encode(bytes).as_bytes())?;
+    Ok(())
+}
+
+// endregion
@@ -0,0 +1,82 @@
+use crate::settings::{self, MenuItem, MenuSettings};
+use crate::utils::Payload;
+use tauri::AppHandle;
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+
+// region: Policy
+
+/// Decide whether an action requested through `tooly://run` or `--run` may
+/// run, asking the user when the settings require it. Returns the matching
+/// item, if any. Blocks while the dialog is shown, so it must not be called
+/// from the main thread.
+pub fn check(app: &AppHandle, payload: &Payload) -> Result<Option<MenuItem>, String> {
+    let settings = settings::load_or_default(app);
+    let policy = &settings.policy;
+    let (question, item) = match find_item(&settings, payload) {
+        Some((id, item)) => {
+            let allowlisted = policy.allowlist.is_empty() || policy.allowlist.contains(id);
+            if !item.confirm && allowlisted {
+                return Ok(Some(item.clone()));
+            }
+            (format!("Run '{id}' on {}?", describe(payload)), Some(item))
+        }
+        None if policy.deny_unknown => {
+            return Err(format!(
+                "'{}' action is not in the settings file.",
+                payload.action_type
+            ));
+        }
+        None => (
+            format!(
+                "An unknown '{}' action was requested on {}:\n\n{}\n\nRun it?",
+                payload.action_type,
+                describe(payload),
+                payload.action
+            ),
+            None,
+        ),
+    };
+    let confirmed = app
+        .dialog()
+        .message(question)
+        .title("Tooly")
+        .kind(MessageDialogKind::Warning)
+        .buttons(MessageDialogButtons::OkCancelCustom(
+            "Run".to_string(),
+            "Cancel".to_string(),
+        ))
+        .blocking_show();
+    if confirmed {
+        Ok(item.cloned())
+    } else {
+        Err("Cancelled by user.".to_string())
+    }
+}
+
+// endregion
+
+// region: Utils
+
+/// Find the enabled item a payload was built from. Payloads only carry the
+/// item fields, so items are matched on those.
+fn find_item<'a>(
+    settings: &'a MenuSettings,
+    payload: &Payload,
+) -> Option<(&'a String, &'a MenuItem)> {
+    settings.items.iter().find(|(_, item)| {
+        item.enabled
+            && item.action_type == payload.action_type
+            && item.action == payload.action
+            && item.target_type == payload.target_type
+    })
+}
+
+/// Short description of the selection for dialogs.
+fn describe(payload: &Payload) -> String {
+    match payload.items.as_slice() {
+        [item] => format!("'{item}'"),
+        items => format!("{} items in '{}'", items.len(), payload.target),
+    }
+}
+
+// endregion
@@ -0,0 +1,230 @@
+use crate::settings::{ClipboardSettings, SecretMode};
+use once_cell::sync::Lazy;
+use regex::{Regex, RegexSet};
+use std::process::Command;
+use std::sync::Mutex;
+
+// region: Variables
+
+/// Built-in secret patterns, by kind. Card numbers are checked separately.
+static SECRET_PATTERNS: Lazy<Vec<(&str, Regex)>> = Lazy::new(|| {
+    [
+        (
+            "private key",
+            r"-----BEGIN [A-Z ]*PRIVATE KEY-----[\s\S]*?(?:-----END [A-Z ]*PRIVATE KEY-----|\z)",
+        ),
+        (
+            "jwt",
+            r"\beyJ[A-Za-z0-9_-]+\.eyJ[A-Za-z0-9_-]+\.[A-Za-z0-9_-]+",
+        ),
+        ("aws key", r"\b(?:AKIA|ASIA)[0-9A-Z]{16}\b"),
+        (
+            "github token",
+            r"\b(?:gh[pousr]_[A-Za-z0-9]{36,}|github_pat_[A-Za-z0-9_]{22,})",
+        ),
+        ("slack token", r"\bxox[abprs]-[A-Za-z0-9-]{10,}"),
+        ("google key", r"\bAIza[0-9A-Za-z_-]{35}"),
+        ("stripe key", r"\b[rs]k_(?:live|test)_[0-9A-Za-z]{16,}"),
+        ("api key", r"\bsk-[A-Za-z0-9_-]{20,}"),
+    ]
+    .into_iter()
+    .map(|(kind, pattern)| (kind, Regex::new(pattern).unwrap()))
+    .collect()
+});
+
+/// Runs of 13 to 19 digits, optionally grouped by spaces or dashes.
+static CARD_PATTERN: Lazy<Regex> = Lazy::new(|| Regex::new(r"\b\d(?:[ -]?\d){12,18}\b").unwrap());
+
+/// Text replacing redacted secrets.
+const REDACTED: &str = "[redacted]";
+
+/// Custom secret patterns, compiled when the settings change.
+static CUSTOM_PATTERNS: Mutex<Option<CustomPatterns>> = Mutex::new(None);
+
+/// Clipboard formats marking data as concealed or transient (password
+/// managers):
+///
+/// - macOS: `org.nspasteboard.ConcealedType` and `TransientType`.
+/// - Windows: `ExcludeClipboardContentFromMonitorProcessing`.
+/// - Linux: `x-kde-passwordManagerHint`.
+pub const CONCEALED_TYPES: [&str; 4] = [
+    "org.nspasteboard.ConcealedType",
+    "org.nspasteboard.TransientType",
+    "ExcludeClipboardContentFromMonitorProcessing",
+    "x-kde-passwordManagerHint",
+];
+
+// endregion
+
+// region: Structs
+
+/// What to do with copied text.
+#[derive(Debug, PartialEq)]
+pub enum Verdict {
+    /// No secret found.
+    Keep,
+    /// Secrets found, record this text as a sensitive entry.
+    Sensitive(String),
+    /// Do not record, with the reason.
+    Skip(String),
+}
+
+/// Custom patterns, matched together and replaced one by one.
+struct CustomPatterns {
+    set: RegexSet,
+    regexes: Vec<Regex>,
+}
+
+// endregion
+
+// region: Privacy
+
+/// Compile the custom secret patterns of new clipboard settings.
+pub fn configure(config: &ClipboardSettings) {
+    let patterns = &config.secret_patterns;
+    let compiled = RegexSet::new(patterns).and_then(|set| {
+        let regexes = patterns
+            .iter()
+            .map(|p| Regex::new(p))
+            .collect::<Result<_, _>>()?;
+        Ok(CustomPatterns { set, regexes })
+    });
+    *CUSTOM_PATTERNS.lock().unwrap() = match compiled {
+        Ok(custom) => Some(custom),
+        Err(e) => {
+            eprintln!("Privacy - Ignored invalid secret patterns: {e}");
+            None
+        }
+    };
+}
+
+/// Check whether the source application is ignored (case-insensitive).
+pub fn is_ignored(source: Option<&str>, config: &ClipboardSettings) -> bool {
+    source.is_some_and(|source| {
+        config
+            .ignored_apps
+            .iter()
+            .any(|app| app.eq_ignore_ascii_case(source))
+    })
+}
+
+/// Look for secrets in copied text and decide how to record it. Custom
+/// patterns are the ones compiled by `configure`.
+pub fn scan(text: &str, config: &ClipboardSettings) -> Verdict {
+    let mut redacted = text.to_string();
+    let mut kinds = Vec::new();
+    for (kind, regex) in SECRET_PATTERNS.iter() {
+        if regex.is_match(&redacted) {
+            kinds.push(*kind);
+            redacted = regex.replace_all(&redacted, REDACTED).to_string();
+        }
+    }
+    if let Some(custom) = CUSTOM_PATTERNS.lock().unwrap().as_ref() {
+        let matches = custom.set.matches(&redacted);
+        if matches.matched_any() {
+            kinds.push("custom");
+        }
+        for index in matches.iter() {
+            redacted = custom.regexes[index]
+                .replace_all(&redacted, REDACTED)
+                .to_string();
+        }
+    }
+    let cards = CARD_PATTERN
+        .find_iter(&redacted)
+        .filter(|m| luhn(m.as_str()));
+    let cards: Vec<(usize, usize)> = cards.map(|m| (m.start(), m.end())).collect();
+    if !cards.is_empty() {
+        kinds.push("card number");
+        for (start, end) in cards.into_iter().rev() {
+            redacted.replace_range(start..end, REDACTED);
+        }
+    }
+    if kinds.is_empty() {
+        return Verdict::Keep;
+    }
+    match config.secrets {
+        SecretMode::Skip => Verdict::Skip(kinds.join(", ")),
+        SecretMode::Redact => Verdict::Sensitive(redacted),
+        SecretMode::Expire => Verdict::Sensitive(text.to_string()),
+    }
+}
+
+/// Check whether the clipboard holds data marked as concealed or transient
+/// by the application that copied it, by listing its formats with a helper
+/// process. Used when the clipboard cannot be watched natively, see
+/// `CONCEALED_TYPES`. On Windows, `CanIncludeInClipboardHistory` set to 0
+/// also conceals data.
+pub fn is_concealed() -> bool {
+    #[cfg(target_os = "macos")]
+    let output = Command::new("osascript")
+        .args([
+            "-l",
+            "JavaScript",
+            "-e",
+            "ObjC.import('AppKit'); \
+            ObjC.deepUnwrap($.NSPasteboard.generalPasteboard.types).join('\\n')",
+        ])
+        .output();
+
+    #[cfg(target_os = "windows")]
+    let output = {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+        Command::new("powershell")
+            .args([
+                "-NoProfile",
+                "-STA",
+                "-Command",
+                "Add-Type -AssemblyName System.Windows.Forms; \
+                $data = [System.Windows.Forms.Clipboard]::GetDataObject(); \
+                if ($data) { \
+                    $formats = $data.GetFormats(); $formats; \
+                    if ($formats -contains 'CanIncludeInClipboardHistory') { \
+                        $value = $data.GetData('CanIncludeInClipboardHistory'); \
+                        if ($value -and $value.ReadByte() -eq 0) { 'ExcludeFromHistory' } \
+                    } \
+                }",
+            ])
+            .creation_flags(CREATE_NO_WINDOW)
+            .output()
+    };
+
+    #[cfg(target_os = "linux")]
+    let output = if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        Command::new("wl-paste").arg("--list-types").output()
+    } else {
+        Command::new("xclip")
+            .args(["-selection", "clipboard", "-o", "-t", "TARGETS"])
+            .output()
+    };
+
+    let Ok(output) = output else {
+        return false;
+    };
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .any(|t| CONCEALED_TYPES.contains(&t.trim()) || t.trim() == "ExcludeFromHistory")
+}
+
+// endregion
+
+// region: Utils
+
+/// Luhn checksum of a card number candidate.
+fn luhn(candidate: &str) -> bool {
+    let digits: Vec<u32> = candidate.chars().filter_map(|c| c.to_digit(10)).collect();
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &d)| match (i % 2, d * 2) {
+            (1, doubled) if doubled > 9 => doubled - 9,
+            (1, doubled) => doubled,
+            _ => d,
+        })
+        .sum();
+    sum % 10 == 0
+}
+
+// endregion
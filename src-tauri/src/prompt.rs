@@ -0,0 +1,151 @@
+use crate::settings::{InputField, InputKind};
+use crate::utils::Payload;
+use crate::{template, templates, windows};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::mpsc::{self, Sender};
+use std::sync::Mutex;
+
+// region: Variables
+
+/// Prompt waiting for an answer, only one is shown at a time.
+static PENDING: Mutex<Option<Pending>> = Mutex::new(None);
+
+// endregion
+
+// region: Structs
+
+/// Fields shown by the prompt window.
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptRequest {
+    /// Selected items the action will run on.
+    pub items: Vec<String>,
+    pub fields: Vec<InputField>,
+}
+
+struct Pending {
+    request: PromptRequest,
+    sender: Sender<Option<HashMap<String, String>>>,
+}
+
+// endregion
+
+// region: Commands
+
+/// Return the fields of the open prompt, if any.
+#[tauri::command]
+pub fn get_prompt() -> Option<PromptRequest> {
+    PENDING.lock().unwrap().as_ref().map(|p| p.request.clone())
+}
+
+/// Answer the open prompt with the entered values (by field name), or
+/// cancel it with `None`. Invalid values keep the prompt open.
+#[tauri::command]
+pub fn submit_prompt(values: Option<HashMap<String, String>>) -> Result<(), String> {
+    let mut pending = PENDING.lock().unwrap();
+    let Some(current) = pending.as_ref() else {
+        return Err("No prompt is open.".to_string());
+    };
+    let values = match values {
+        Some(values) => Some(check_values(&current.request.fields, &values)?),
+        None => None,
+    };
+    if let Some(current) = pending.take() {
+        current.sender.send(values).ok();
+    }
+    drop(pending);
+    windows::hide("prompt");
+    Ok(())
+}
+
+// endregion
+
+// region: Prompts
+
+/// Ask for the inputs of an action: the fields declared by its menu item
+/// and any `{prompt:Label}` placeholder of the action or its template.
+/// Returns the values by name and label, empty when there is nothing to ask,
+/// or `None` when the user cancelled. Blocks until the prompt is answered,
+/// so it must not be called from the main thread.
+pub fn collect(info: &Payload) -> Result<Option<HashMap<String, String>>, String> {
+    let mut fields = info.inputs.clone();
+    let mut labels = template::prompt_labels(&info.action);
+    // Template files may use prompts too.
+    if let Some((name, _)) = templates::parse_action(&info.action) {
+        if info.action_type == "create" {
+            let app = crate::get_app_handle().clone();
+            labels.extend(templates::prompt_labels(&app, name));
+        }
+    }
+    for label in labels {
+        if !fields.iter().any(|f| f.name == label || f.label == label) {
+            fields.push(InputField {
+                name: label,
+                label: String::new(),
+                kind: InputKind::Text,
+                default: String::new(),
+                choices: Vec::new(),
+            });
+        }
+    }
+    if fields.is_empty() {
+        return Ok(Some(HashMap::new()));
+    }
+    let Some(mut values) = ask(PromptRequest {
+        items: info.items.clone(),
+        fields: fields.clone(),
+    })?
+    else {
+        return Ok(None);
+    };
+    for field in fields.iter().filter(|f| !f.label.is_empty()) {
+        if let Some(value) = values.get(&field.name).cloned() {
+            values.entry(field.label.clone()).or_insert(value);
+        }
+    }
+    Ok(Some(values))
+}
+
+/// Cancel the open prompt, called when its window is closed.
+pub fn cancel() {
+    if let Some(pending) = PENDING.lock().unwrap().take() {
+        println!("Prompt - Prompt closed.");
+        pending.sender.send(None).ok();
+    }
+}
+
+/// Show the prompt window and wait for the answer.
+fn ask(request: PromptRequest) -> Result<Option<HashMap<String, String>>, String> {
+    let (sender, receiver) = mpsc::channel();
+    {
+        let mut pending = PENDING.lock().unwrap();
+        if pending.is_some() {
+            return Err("Another prompt is already open.".to_string());
+        }
+        *pending = Some(Pending { request, sender });
+    }
+    windows::open_prompt();
+    // The sender is dropped without an answer if the app quits.
+    Ok(receiver.recv().ok().flatten())
+}
+
+// endregion
+
+// region: Utils
+
+/// Check every field, using its default when no value was entered.
+fn check_values(
+    fields: &[InputField],
+    values: &HashMap<String, String>,
+) -> Result<HashMap<String, String>, String> {
+    fields
+        .iter()
+        .map(|field| {
+            let value = values.get(&field.name).unwrap_or(&field.default);
+            field.check(value).map(|value| (field.name.clone(), value))
+        })
+        .collect()
+}
+
+// endregion
@@ -0,0 +1,317 @@
+use crate::journal::{self, OperationKind};
+use crate::template::{self, Context, Quote};
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use std::{fs, path::Path, path::PathBuf};
+
+// region: Variables
+
+/// Items selected when the find and replace window was last opened.
+static SELECTION: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+// endregion
+
+// region: Structs
+
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RenameOptions {
+    find: String,
+    replace: String,
+    /// Treat `find` as a regular expression (`replace` may use `$1`, `${name}`).
+    regex: bool,
+    case_sensitive: bool,
+    /// Also match and rewrite the file extension.
+    include_extension: bool,
+    case: CaseMode,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum CaseMode {
+    #[default]
+    Keep,
+    Lower,
+    Upper,
+    Title,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct RenamePreview {
+    from: String,
+    to: String,
+    status: RenameStatus,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum RenameStatus {
+    /// Name does not change.
+    Unchanged,
+    /// Ready to be renamed.
+    Ready,
+    /// Resulting name is empty or contains path separators.
+    Invalid,
+    /// Another selected item would get the same name.
+    Duplicate,
+    /// A file that is not part of the selection already has that name.
+    Exists,
+}
+
+// endregion
+
+// region: Commands
+
+/// Return the items the find and replace window was opened with.
+#[tauri::command]
+pub fn rename_selection() -> Vec<String> {
+    SELECTION.lock().unwrap().clone()
+}
+
+/// Compute the new names for the given items without touching the disk.
+#[tauri::command]
+pub fn rename_preview(
+    items: Vec<String>,
+    options: RenameOptions,
+) -> Result<Vec<RenamePreview>, String> {
+    preview(&items, &options)
+}
+
+/// Rename the given items. Nothing is renamed if any item has a conflict.
+#[tauri::command]
+pub fn rename_apply(
+    items: Vec<String>,
+    options: RenameOptions,
+) -> Result<Vec<RenamePreview>, String> {
+    let previews = preview(&items, &options)?;
+    apply(&previews)?;
+    Ok(previews)
+}
+
+// endregion
+
+// region: Engine
+
+/// Remember the selected items for the find and replace window.
+pub fn set_selection(items: &[String]) {
+    *SELECTION.lock().unwrap() = items.to_vec();
+}
+
+/// Build the preview list, flagging invalid names and collisions.
+pub fn preview(items: &[String], options: &RenameOptions) -> Result<Vec<RenamePreview>, String> {
+    let matcher = build_matcher(options)?;
+    let quote = if options.regex {
+        Quote::Replacement
+    } else {
+        Quote::None
+    };
+    let mut previews: Vec<RenamePreview> = Vec::with_capacity(items.len());
+    for (index, item) in items.iter().enumerate() {
+        let from = Path::new(item);
+        // Placeholders such as `{counter:03}` are evaluated per item.
+        let context = Context {
+            target: from
+                .parent()
+                .map(|p| p.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            item: item.clone(),
+            counter: index + 1,
+            ..Context::default()
+        };
+        let replace = template::render(&options.replace, &context, quote)?;
+        let name = from
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let new_name = rename_name(&name, from.is_dir(), &matcher, options, &replace);
+        let to = from.with_file_name(&new_name);
+        let status = if !is_valid_name(&new_name) {
+            RenameStatus::Invalid
+        } else if new_name == name {
+            RenameStatus::Unchanged
+        } else {
+            RenameStatus::Ready
+        };
+        previews.push(RenamePreview {
+            from: item.clone(),
+            to: to.to_string_lossy().to_string(),
+            status,
+        });
+    }
+
+    let sources: HashSet<String> = items.iter().map(|i| path_key(i)).collect();
+    let mut targets: HashMap<String, usize> = HashMap::new();
+    for preview in &previews {
+        if preview.status != RenameStatus::Invalid {
+            *targets.entry(path_key(&preview.to)).or_default() += 1;
+        }
+    }
+    for preview in previews.iter_mut() {
+        if preview.status != RenameStatus::Ready {
+            continue;
+        }
+        let key = path_key(&preview.to);
+        if targets.get(&key).copied().unwrap_or(0) > 1 {
+            preview.status = RenameStatus::Duplicate;
+        } else if Path::new(&preview.to).exists() && !sources.contains(&key) {
+            preview.status = RenameStatus::Exists;
+        }
+    }
+    Ok(previews)
+}
+
+/// Apply a preview list, refusing to touch anything if an item has a conflict.
+pub fn apply(previews: &[RenamePreview]) -> Result<(), String> {
+    if let Some(conflict) = previews
+        .iter()
+        .find(|p| !matches!(p.status, RenameStatus::Ready | RenameStatus::Unchanged))
+    {
+        return Err(format!(
+            "Cannot rename '{}' to '{}' ({:?}).",
+            conflict.from, conflict.to, conflict.status
+        ));
+    }
+    let pairs: Vec<(PathBuf, PathBuf)> = previews
+        .iter()
+        .filter(|p| p.status == RenameStatus::Ready)
+        .map(|p| (PathBuf::from(&p.from), PathBuf::from(&p.to)))
+        .collect();
+    move_all(&pairs)?;
+    journal::record(OperationKind::Rename, &pairs);
+    println!("Rename - Renamed {} item(s).", pairs.len());
+    Ok(())
+}
+
+/// Move every `from` path to its `to` path. Items are first moved to
+/// temporary names so that swaps and chains (a → b, b → c) work, and every
+/// step is rolled back if one of them fails.
+pub fn move_all(pairs: &[(PathBuf, PathBuf)]) -> Result<(), String> {
+    // Steps already performed, as (from, to) pairs, for rollback.
+    let mut done: Vec<(PathBuf, PathBuf)> = Vec::new();
+    let mut staged: Vec<(PathBuf, PathBuf)> = Vec::new();
+
+    for (index, (from, to)) in pairs.iter().enumerate() {
+        let temp = from.with_file_name(format!(".tooly-rename-{}-{}", std::process::id(), index));
+        if let Err(e) = fs::rename(from, &temp) {
+            rollback(&done);
+            return Err(format!("Failed to rename '{}': {e}", from.display()));
+        }
+        done.push((from.clone(), temp.clone()));
+        staged.push((temp, to.clone()));
+    }
+    for (temp, to) in staged {
+        // Re-check: something may have appeared since the preview was computed.
+        if to.exists() {
+            rollback(&done);
+            return Err(format!("'{}' already exists.", to.display()));
+        }
+        if let Err(e) = fs::rename(&temp, &to) {
+            rollback(&done);
+            return Err(format!("Failed to rename to '{}': {e}", to.display()));
+        }
+        done.push((temp, to));
+    }
+    Ok(())
+}
+
+// endregion
+
+// region: Utils
+
+/// Key comparing paths the way the file system does: case-insensitively on
+/// macOS and Windows, whose volumes usually are, exactly on Linux.
+fn path_key(path: &str) -> String {
+    if cfg!(any(target_os = "macos", target_os = "windows")) {
+        path.to_lowercase()
+    } else {
+        path.to_string()
+    }
+}
+
+/// Undo performed rename steps in reverse order.
+fn rollback(done: &[(PathBuf, PathBuf)]) {
+    for (from, to) in done.iter().rev() {
+        if let Err(e) = fs::rename(to, from) {
+            eprintln!(
+                "Rename - Failed to roll back '{}' to '{}': {e}",
+                to.display(),
+                from.display()
+            );
+        }
+    }
+}
+
+/// Compile the find pattern, escaping it unless regex mode is enabled.
+fn build_matcher(options: &RenameOptions) -> Result<Option<Regex>, String> {
+    if options.find.is_empty() {
+        return Ok(None);
+    }
+    let pattern = if options.regex {
+        options.find.clone()
+    } else {
+        regex::escape(&options.find)
+    };
+    RegexBuilder::new(&pattern)
+        .case_insensitive(!options.case_sensitive)
+        .build()
+        .map(Some)
+        .map_err(|e| format!("Invalid pattern: {e}"))
+}
+
+/// Compute the new file name for a single item.
+fn rename_name(
+    name: &str,
+    is_dir: bool,
+    matcher: &Option<Regex>,
+    options: &RenameOptions,
+    replace: &str,
+) -> String {
+    // Split off the extension unless it should be rewritten too.
+    let (stem, extension) = match name.rfind('.') {
+        Some(dot) if !options.include_extension && !is_dir && dot > 0 => name.split_at(dot),
+        _ => (name, ""),
+    };
+    let replaced = match matcher {
+        // Literal mode must not expand `$` in the replacement.
+        Some(regex) if options.regex => regex.replace_all(stem, replace),
+        Some(regex) => regex.replace_all(stem, regex::NoExpand(replace)),
+        None => stem.into(),
+    };
+    format!("{}{}", change_case(&replaced, options.case), extension)
+}
+
+/// Apply the selected case conversion.
+fn change_case(text: &str, case: CaseMode) -> String {
+    match case {
+        CaseMode::Keep => text.to_string(),
+        CaseMode::Lower => text.to_lowercase(),
+        CaseMode::Upper => text.to_uppercase(),
+        CaseMode::Title => {
+            let mut result = String::with_capacity(text.len());
+            let mut word_start = true;
+            for c in text.chars() {
+                if word_start {
+                    result.extend(c.to_uppercase());
+                } else {
+                    result.extend(c.to_lowercase());
+                }
+                word_start = !c.is_alphanumeric();
+            }
+            result
+        }
+    }
+}
+
+/// Check that a name can be used as a single path component.
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name != "."
+        && name != ".."
+        && !name.contains('/')
+        && !(cfg!(target_os = "windows")
+            && name.contains(['\\', ':', '*', '?', '"', '<', '>', '|']))
+}
+
+// endregion
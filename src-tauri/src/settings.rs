@@ -0,0 +1,853 @@
+use crate::{clipboard, shortcuts, transforms};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashSet};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+use std::{fmt, fs, path::Path, path::PathBuf, thread};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_global_shortcut::Shortcut;
+use tauri_plugin_notification::NotificationExt;
+
+// region: Variables
+
+/// Current `settings.json` schema version.
+pub const SETTINGS_VERSION: u32 = 1;
+
+/// Folder shared with the Finder extension (`Application Support/Tooly`).
+const SETTINGS_FOLDER: &str = "Tooly";
+
+/// Order entry used to place a separator between items.
+pub const SEPARATOR: &str = "%sprt%";
+
+/// Items running at once in parallel mode, unless the item sets its own.
+const DEFAULT_WORKERS: usize = 4;
+
+/// How often the settings file is checked for changes.
+const WATCH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Last settings loaded or saved successfully, used while the file is invalid.
+static LAST_GOOD: Mutex<Option<MenuSettings>> = Mutex::new(None);
+
+const ACTION_TYPES: [&str; 8] = [
+    "create",
+    "app",
+    "shortcut",
+    "terminal",
+    "script",
+    "replace",
+    "copy",
+    "clipboard",
+];
+const ICON_TYPES: [&str; 4] = ["", "app", "image", "symbol"];
+/// Terminals for terminal actions (see `terminal.rs`), "" picks one.
+const TERMINALS: [&str; 12] = [
+    "",
+    "wt",
+    "pwsh",
+    "cmd",
+    "gnome-terminal",
+    "konsole",
+    "kitty",
+    "alacritty",
+    "wezterm",
+    "xterm",
+    "terminal",
+    "iterm",
+];
+/// Script shells, "" uses bash (cmd on Windows).
+const SHELLS: [&str; 7] = ["", "bash", "zsh", "sh", "pwsh", "python", "cmd"];
+
+// endregion
+
+// region: Structs
+
+/// Menu configuration, mirrors `MenuSettings` in `SettingsManager.swift`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct MenuSettings {
+    pub version: u32,
+    /// Item ids in menu order, `%sprt%` for separators.
+    pub order: Vec<String>,
+    pub groups: BTreeMap<String, MenuGroup>,
+    pub items: BTreeMap<String, MenuItem>,
+    pub separators: bool,
+    /// Accelerator (e.g. "CmdOrCtrl+Shift+N") → menu item id.
+    #[serde(default)]
+    pub shortcuts: BTreeMap<String, String>,
+    /// Rules for actions requested through `tooly://run`.
+    #[serde(default)]
+    pub policy: Policy,
+    /// Terminal used by terminal actions, "" to pick one. Windows: "wt",
+    /// "pwsh" or "cmd". Linux: "gnome-terminal", "konsole", "kitty",
+    /// "alacritty", "wezterm" or "xterm". macOS: "terminal" or "iterm".
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub terminal: String,
+    /// Clipboard history.
+    #[serde(default)]
+    pub clipboard: ClipboardSettings,
+}
+
+/// Mirrors `MenuGroup` in `SettingsManager.swift`.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct MenuGroup {
+    pub icon_type: String,
+    pub icon: String,
+}
+
+/// Mirrors `MenuItem` in `SettingsManager.swift`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct MenuItem {
+    pub group: String,
+    /// "any", "file", "folder" or a comma separated list of extensions.
+    pub target_type: String,
+    pub icon_type: String,
+    pub icon: String,
+    pub action_type: String,
+    pub action: String,
+    pub key: String,
+    pub enabled: bool,
+    /// Ask before running when requested through `tooly://run`.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub confirm: bool,
+    /// How scripts run (ignored by other action types).
+    #[serde(flatten)]
+    pub options: ScriptOptions,
+    /// Values asked for before the action runs.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub inputs: Vec<InputField>,
+}
+
+/// Value asked for in the prompt window. Available to the action as the
+/// `{prompt:name}` placeholder and the `name` environment variable.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct InputField {
+    pub name: String,
+    /// Text shown next to the field, the name when empty.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub label: String,
+    pub kind: InputKind,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub default: String,
+    /// Values offered by choice fields.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub choices: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum InputKind {
+    Text,
+    Number,
+    Choice,
+    /// "true" or "false".
+    Checkbox,
+    /// Path picked with a file dialog.
+    File,
+}
+
+/// Script execution settings of a menu item.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ScriptOptions {
+    /// Seconds before the script is stopped, 0 for no limit (default 120).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+    /// Extra environment variables.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+    /// Working directory: "target" (default), "parent" of the first
+    /// selected item, or an absolute path.
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub cwd: String,
+    /// One of `SHELLS`.
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub shell: String,
+    /// Open the live log window when the script starts.
+    #[serde(skip_serializing_if = "is_false")]
+    pub log: bool,
+    /// Run once for all items, or once per item (scripts and terminals).
+    #[serde(skip_serializing_if = "ExecMode::is_batch")]
+    pub mode: ExecMode,
+    /// Items running at once in parallel mode (default 4).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub workers: Option<usize>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExecMode {
+    /// All items at once, as arguments of a single run.
+    #[default]
+    Batch,
+    /// One run per item, one after the other.
+    Sequential,
+    /// One run per item, several at once.
+    Parallel,
+}
+
+/// Clipboard history settings.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ClipboardSettings {
+    /// Record copied text, images and files.
+    pub enabled: bool,
+    /// Unpinned entries kept, the least recently used are removed first.
+    pub max_entries: usize,
+    /// Days unpinned entries are kept, 0 for no limit.
+    pub max_days: u64,
+    /// Applications whose copies are not recorded, by name as shown in
+    /// entry sources (case-insensitive).
+    pub ignored_apps: Vec<String>,
+    /// What to do with text holding secrets (keys, tokens, card numbers).
+    pub secrets: SecretMode,
+    /// Extra regular expressions matching secrets.
+    pub secret_patterns: Vec<String>,
+    /// Minutes unpinned entries holding secrets are kept, 0 for no limit.
+    pub sensitive_minutes: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SecretMode {
+    /// Do not record the text.
+    Skip,
+    /// Record the text with secrets replaced.
+    #[default]
+    Redact,
+    /// Record the text as is, it expires after `sensitiveMinutes`.
+    Expire,
+}
+
+/// Rules applied to actions requested through `tooly://run`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Policy {
+    /// Item ids that run without prompting. When not empty, other items
+    /// ask for confirmation first.
+    pub allowlist: Vec<String>,
+    /// Reject actions that match no item in the settings file instead of
+    /// asking for confirmation.
+    pub deny_unknown: bool,
+}
+
+#[derive(Debug)]
+pub enum SettingsError {
+    Io(std::io::Error),
+    Parse(serde_json::Error),
+    /// File was written by a newer version of Tooly.
+    UnsupportedVersion(u32),
+    /// Validation problems, each prefixed with the offending field path.
+    Invalid(Vec<String>),
+}
+
+impl fmt::Display for SettingsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SettingsError::Io(e) => write!(f, "Failed to access settings: {e}"),
+            SettingsError::Parse(e) => write!(f, "Failed to decode settings: {e}"),
+            SettingsError::UnsupportedVersion(v) => write!(
+                f,
+                "Settings version {v} is newer than supported version {SETTINGS_VERSION}."
+            ),
+            SettingsError::Invalid(problems) => {
+                write!(f, "Invalid settings: {}", problems.join("; "))
+            }
+        }
+    }
+}
+
+impl std::error::Error for SettingsError {}
+
+impl From<std::io::Error> for SettingsError {
+    fn from(e: std::io::Error) -> Self {
+        SettingsError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for SettingsError {
+    fn from(e: serde_json::Error) -> Self {
+        SettingsError::Parse(e)
+    }
+}
+
+impl Default for MenuSettings {
+    fn default() -> Self {
+        MenuSettings {
+            version: SETTINGS_VERSION,
+            order: Vec::new(),
+            groups: BTreeMap::new(),
+            items: BTreeMap::new(),
+            separators: true,
+            shortcuts: BTreeMap::new(),
+            policy: Policy::default(),
+            terminal: String::new(),
+            clipboard: ClipboardSettings::default(),
+        }
+    }
+}
+
+impl ScriptOptions {
+    /// Number of items running at once in per-item modes.
+    pub fn worker_limit(&self) -> usize {
+        match self.mode {
+            ExecMode::Parallel => self.workers.unwrap_or(DEFAULT_WORKERS),
+            _ => 1,
+        }
+    }
+
+    /// Check the options, errors start with the offending field name.
+    pub fn check(&self) -> Result<(), String> {
+        if !SHELLS.contains(&self.shell.as_str()) {
+            return Err(format!(
+                "shell: expected one of bash, zsh, sh, pwsh, python or cmd, got '{}'",
+                self.shell
+            ));
+        }
+        if !matches!(self.cwd.as_str(), "" | "target" | "parent")
+            && !Path::new(&self.cwd).is_absolute()
+        {
+            return Err(format!(
+                "cwd: expected 'target', 'parent' or an absolute path, got '{}'",
+                self.cwd
+            ));
+        }
+        if self.workers == Some(0) {
+            return Err("workers: must be at least 1".to_string());
+        }
+        for name in self.env.keys() {
+            if name.is_empty() || name.contains(['=', '\0']) {
+                return Err(format!("env: invalid variable name '{name}'"));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl InputField {
+    /// Check an entered value, returning it normalized.
+    pub fn check(&self, value: &str) -> Result<String, String> {
+        let label = if self.label.is_empty() {
+            &self.name
+        } else {
+            &self.label
+        };
+        match self.kind {
+            InputKind::Text | InputKind::File => Ok(value.to_string()),
+            InputKind::Number => value
+                .trim()
+                .parse::<f64>()
+                .map(|_| value.trim().to_string())
+                .map_err(|_| format!("'{label}' must be a number.")),
+            InputKind::Choice if self.choices.iter().any(|c| c == value) => Ok(value.to_string()),
+            InputKind::Choice => Err(format!("'{label}' must be one of the listed values.")),
+            InputKind::Checkbox => match value {
+                "true" | "false" => Ok(value.to_string()),
+                "" => Ok("false".to_string()),
+                _ => Err(format!("'{label}' must be true or false.")),
+            },
+        }
+    }
+
+    /// Check the field definition, errors start with the offending field name.
+    fn validate(&self) -> Result<(), String> {
+        let valid_name = self
+            .name
+            .starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+            && self
+                .name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !valid_name {
+            return Err(format!(
+                "name: expected letters, digits and '_', got '{}'",
+                self.name
+            ));
+        }
+        if self.kind == InputKind::Choice && self.choices.is_empty() {
+            return Err("choices: choice fields need at least one value".to_string());
+        }
+        if !self.default.is_empty() {
+            self.check(&self.default)
+                .map_err(|e| format!("default: {e}"))?;
+        }
+        Ok(())
+    }
+}
+
+impl ExecMode {
+    fn is_batch(&self) -> bool {
+        *self == ExecMode::Batch
+    }
+}
+
+impl Default for ClipboardSettings {
+    fn default() -> Self {
+        ClipboardSettings {
+            enabled: true,
+            max_entries: 500,
+            max_days: 30,
+            ignored_apps: Vec::new(),
+            secrets: SecretMode::Redact,
+            secret_patterns: Vec::new(),
+            sensitive_minutes: 10,
+        }
+    }
+}
+
+impl Default for Policy {
+    fn default() -> Self {
+        Policy {
+            allowlist: Vec::new(),
+            deny_unknown: true,
+        }
+    }
+}
+
+// endregion
+
+// region: Commands
+
+/// Return the current settings.
+#[tauri::command]
+pub fn get_settings(app: AppHandle) -> Result<MenuSettings, String> {
+    load(&app).map_err(|e| e.to_string())
+}
+
+/// Validate and save settings.
+#[tauri::command]
+pub fn save_settings(app: AppHandle, settings: MenuSettings) -> Result<(), String> {
+    save(&app, settings).map_err(|e| e.to_string())
+}
+
+// endregion
+
+// region: Settings
+
+/// Load, migrate and validate the settings file. A missing file yields the
+/// default settings.
+pub fn load(app: &AppHandle) -> Result<MenuSettings, SettingsError> {
+    load_file(&settings_path(app))
+}
+
+/// Load the settings, falling back (and logging) on errors to the last good
+/// settings, or to defaults if there are none.
+pub fn load_or_default(app: &AppHandle) -> MenuSettings {
+    or_last_good(load(app))
+}
+
+/// Load a settings file and remember it as the last good settings.
+fn load_file(path: &Path) -> Result<MenuSettings, SettingsError> {
+    let settings = if path.exists() {
+        parse(&fs::read_to_string(path)?)?
+    } else {
+        MenuSettings::default()
+    };
+    *LAST_GOOD.lock().unwrap() = Some(settings.clone());
+    Ok(settings)
+}
+
+/// Return loaded settings, or the last good ones when loading failed.
+fn or_last_good(result: Result<MenuSettings, SettingsError>) -> MenuSettings {
+    result.unwrap_or_else(|e| {
+        eprintln!("Settings - {e}");
+        LAST_GOOD.lock().unwrap().clone().unwrap_or_default()
+    })
+}
+
+/// Decode settings from JSON, migrating older versions.
+pub fn parse(json: &str) -> Result<MenuSettings, SettingsError> {
+    let mut value: Value = serde_json::from_str(json)?;
+    migrate(&mut value)?;
+    let settings: MenuSettings = serde_json::from_value(value)?;
+    validate(&settings)?;
+    Ok(settings)
+}
+
+/// Validate and write settings atomically (temporary file + rename), so the
+/// Finder extension never reads a partially written file.
+pub fn save(app: &AppHandle, mut settings: MenuSettings) -> Result<(), SettingsError> {
+    settings.version = SETTINGS_VERSION;
+    validate(&settings)?;
+    let path = settings_path(app);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let temp = path.with_extension("json.tmp");
+    fs::write(&temp, serde_json::to_string_pretty(&settings)?)?;
+    fs::rename(&temp, &path)?;
+    *LAST_GOOD.lock().unwrap() = Some(settings);
+    println!("Settings - Saved settings.");
+    Ok(())
+}
+
+/// Write the built-in settings if no settings file exists yet.
+pub fn ensure_exists(app: &AppHandle) {
+    if settings_path(app).exists() {
+        return;
+    }
+    if let Err(e) = save(app, builtin()) {
+        eprintln!("Settings - Failed to create settings: {e}");
+    }
+}
+
+/// Return the built-in menu used on first run and when resetting.
+pub fn builtin() -> MenuSettings {
+    let item = |target_type: &str, icon: &str, action_type: &str, action: &str| MenuItem {
+        group: String::new(),
+        target_type: target_type.to_string(),
+        icon_type: "symbol".to_string(),
+        icon: icon.to_string(),
+        action_type: action_type.to_string(),
+        action: action.to_string(),
+        key: String::new(),
+        enabled: true,
+        confirm: false,
+        options: ScriptOptions::default(),
+        inputs: Vec::new(),
+    };
+    let items = [
+        ("Copy Path", item("any", "doc.on.doc", "copy", "")),
+        (
+            "New Text File",
+            item("any", "doc.badge.plus", "create", "New File.txt|"),
+        ),
+        (
+            "Open Terminal Here",
+            item("any", "terminal", "terminal", "exec \"${SHELL:-bash}\" -l"),
+        ),
+        ("Rename", item("any", "pencil", "replace", "")),
+    ];
+    MenuSettings {
+        order: items.iter().map(|(id, _)| id.to_string()).collect(),
+        items: items
+            .into_iter()
+            .map(|(id, item)| (id.to_string(), item))
+            .collect(),
+        ..MenuSettings::default()
+    }
+}
+
+/// Check settings for problems, reporting every one of them.
+pub fn validate(settings: &MenuSettings) -> Result<(), SettingsError> {
+    let mut problems = Vec::new();
+    let mut seen = HashSet::new();
+    for (index, id) in settings.order.iter().enumerate() {
+        if id == SEPARATOR {
+            continue;
+        }
+        if !settings.items.contains_key(id) {
+            problems.push(format!("order[{index}]: unknown item '{id}'"));
+        } else if !seen.insert(id) {
+            problems.push(format!("order[{index}]: duplicate item '{id}'"));
+        }
+    }
+    for (id, group) in &settings.groups {
+        if !ICON_TYPES.contains(&group.icon_type.as_str()) {
+            problems.push(format!(
+                "groups.'{id}'.iconType: unknown icon type '{}'",
+                group.icon_type
+            ));
+        }
+    }
+    for (id, item) in &settings.items {
+        if id.is_empty() {
+            problems.push("items: item id cannot be empty".to_string());
+        }
+        if !ACTION_TYPES.contains(&item.action_type.as_str()) {
+            problems.push(format!(
+                "items.'{id}'.actionType: unknown action type '{}'",
+                item.action_type
+            ));
+        } else if item.action.trim().is_empty()
+            && !matches!(item.action_type.as_str(), "replace" | "copy")
+        {
+            problems.push(format!("items.'{id}'.action: cannot be empty"));
+        } else if item.action_type == "clipboard" {
+            if let Err(e) = transforms::check_action(&item.action) {
+                problems.push(format!("items.'{id}'.action: {e}"));
+            }
+        }
+        if !ICON_TYPES.contains(&item.icon_type.as_str()) {
+            problems.push(format!(
+                "items.'{id}'.iconType: unknown icon type '{}'",
+                item.icon_type
+            ));
+        }
+        if !is_valid_target_type(&item.target_type) {
+            problems.push(format!(
+                "items.'{id}'.targetType: expected 'any', 'file', 'folder' or extensions, got '{}'",
+                item.target_type
+            ));
+        }
+        if let Err(e) = item.options.check() {
+            problems.push(format!("items.'{id}'.{e}"));
+        }
+        let mut names = HashSet::new();
+        for (index, field) in item.inputs.iter().enumerate() {
+            if let Err(e) = field.validate() {
+                problems.push(format!("items.'{id}'.inputs[{index}].{e}"));
+            } else if !names.insert(&field.name) {
+                problems.push(format!(
+                    "items.'{id}'.inputs[{index}].name: duplicate field '{}'",
+                    field.name
+                ));
+            }
+        }
+        if item.key.chars().count() > 1 {
+            problems.push(format!(
+                "items.'{id}'.key: expected a single character, got '{}'",
+                item.key
+            ));
+        }
+    }
+    for (accelerator, id) in &settings.shortcuts {
+        if let Err(e) = accelerator.parse::<Shortcut>() {
+            problems.push(format!("shortcuts.'{accelerator}': {e}"));
+        }
+        if !settings.items.contains_key(id) {
+            problems.push(format!("shortcuts.'{accelerator}': unknown item '{id}'"));
+        }
+    }
+    if !TERMINALS.contains(&settings.terminal.as_str()) {
+        problems.push(format!(
+            "terminal: unknown terminal '{}'",
+            settings.terminal
+        ));
+    }
+    if settings.clipboard.max_entries == 0 {
+        problems.push("clipboard.maxEntries: must be at least 1".to_string());
+    }
+    for (index, pattern) in settings.clipboard.secret_patterns.iter().enumerate() {
+        if let Err(e) = Regex::new(pattern) {
+            problems.push(format!("clipboard.secretPatterns[{index}]: {e}"));
+        }
+    }
+    for (index, id) in settings.policy.allowlist.iter().enumerate() {
+        if !settings.items.contains_key(id) {
+            problems.push(format!("policy.allowlist[{index}]: unknown item '{id}'"));
+        }
+    }
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(SettingsError::Invalid(problems))
+    }
+}
+
+/// Poll the settings file and notify dependents when it changes.
+pub fn watch(app: &AppHandle) {
+    let app = app.clone();
+    let path = settings_path(&app);
+    thread::spawn(move || {
+        let mut last = modified(&path);
+        loop {
+            thread::sleep(WATCH_INTERVAL);
+            let current = modified(&path);
+            if current != last {
+                last = current;
+                println!("Settings - Settings changed, reloading.");
+                on_change(&app);
+            }
+        }
+    });
+}
+
+/// Refresh everything derived from the settings file. Invalid settings are
+/// reported and leave the current configuration in place.
+fn on_change(app: &AppHandle) {
+    if let Err(e) = load(app) {
+        eprintln!("Settings - Kept previous settings: {e}");
+        let result = app
+            .notification()
+            .builder()
+            .title("Settings not applied")
+            .body(e.to_string())
+            .show();
+        if let Err(e) = result {
+            eprintln!("Settings - Failed to show notification: {e}");
+        }
+        return;
+    }
+    shortcuts::register_all(app);
+    clipboard::configure(&load_or_default(app).clipboard);
+    #[cfg(target_os = "linux")]
+    crate::linux_menu::sync(app);
+    #[cfg(target_os = "windows")]
+    if let Err(e) = crate::explorer::install(app) {
+        eprintln!("Settings - Failed to update Explorer menu: {e}");
+    }
+}
+
+// endregion
+
+// region: Migrations
+
+/// Upgrade a raw settings value to the current version in place.
+fn migrate(value: &mut Value) -> Result<(), SettingsError> {
+    let Some(object) = value.as_object_mut() else {
+        return Err(SettingsError::Invalid(vec![
+            "settings must be a JSON object".to_string(),
+        ]));
+    };
+    // Files written before versioning have no version field.
+    let version = object.get("version").and_then(Value::as_u64).unwrap_or(0) as u32;
+    if version > SETTINGS_VERSION {
+        return Err(SettingsError::UnsupportedVersion(version));
+    }
+    if version < 1 {
+        migrate_v0(object);
+    }
+    Ok(())
+}
+
+/// v0 → v1: add version and shortcuts, fill fields older editors omitted.
+fn migrate_v0(object: &mut serde_json::Map<String, Value>) {
+    object.insert("version".into(), Value::from(1));
+    object
+        .entry("order")
+        .or_insert_with(|| Value::Array(Vec::new()));
+    object
+        .entry("groups")
+        .or_insert_with(|| Value::Object(Default::default()));
+    object
+        .entry("items")
+        .or_insert_with(|| Value::Object(Default::default()));
+    object.entry("separators").or_insert(Value::Bool(true));
+    object
+        .entry("shortcuts")
+        .or_insert_with(|| Value::Object(Default::default()));
+    if let Some(items) = object.get_mut("items").and_then(Value::as_object_mut) {
+        for item in items.values_mut().filter_map(Value::as_object_mut) {
+            for field in ["group", "iconType", "icon", "key"] {
+                item.entry(field).or_insert_with(|| Value::from(""));
+            }
+            item.entry("targetType")
+                .or_insert_with(|| Value::from("any"));
+            item.entry("enabled").or_insert(Value::Bool(true));
+        }
+    }
+    if let Some(groups) = object.get_mut("groups").and_then(Value::as_object_mut) {
+        for group in groups.values_mut().filter_map(Value::as_object_mut) {
+            group.entry("iconType").or_insert_with(|| Value::from(""));
+            group.entry("icon").or_insert_with(|| Value::from(""));
+        }
+    }
+}
+
+// endregion
+
+// region: Utils
+
+/// Return the folder shared with the Finder extension.
+pub fn settings_dir(app: &AppHandle) -> PathBuf {
+    app.path().data_dir().unwrap().join(SETTINGS_FOLDER)
+}
+
+/// Return the settings file shared with the Finder extension.
+pub fn settings_path(app: &AppHandle) -> PathBuf {
+    settings_dir(app).join("settings.json")
+}
+
+/// Check a target type: "any", "file", "folder" or "ext1,ext2".
+fn is_valid_target_type(target_type: &str) -> bool {
+    match target_type {
+        "any" | "file" | "folder" => true,
+        _ => target_type.split(',').all(|ext| {
+            !ext.is_empty()
+                && ext
+                    .chars()
+                    .all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+        }),
+    }
+}
+
+/// Used to omit unset flags from the settings file.
+fn is_false(value: &bool) -> bool {
+    !value
+}
+
+/// Return the modification time of a file, if it exists.
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+// endregion
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn migrates_v0_settings() {
+        let settings = parse(
+            r#"{
+                "order": ["Hello", "%sprt%"],
+                "items": {"Hello": {"actionType": "script", "action": "echo hi"}},
+                "groups": {"Tools": {}}
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(settings.version, SETTINGS_VERSION);
+        assert!(settings.separators);
+        assert!(settings.shortcuts.is_empty());
+        let item = &settings.items["Hello"];
+        assert_eq!(item.target_type, "any");
+        assert!(item.enabled);
+        assert_eq!(item.group, "");
+        assert_eq!(settings.groups["Tools"], MenuGroup::default());
+    }
+
+    #[test]
+    fn rejects_invalid_items() {
+        let json = r#"{
+            "version": 1,
+            "order": ["Bad", "Missing"],
+            "groups": {},
+            "items": {"Bad": {
+                "group": "", "targetType": "PNG, jpg", "iconType": "", "icon": "",
+                "actionType": "script:bash", "action": "echo", "key": "ab",
+                "enabled": true, "shell": "fish"
+            }},
+            "separators": true
+        }"#;
+        let Err(SettingsError::Invalid(problems)) = parse(json) else {
+            panic!("invalid settings were accepted");
+        };
+        for field in [
+            "order[1]",
+            "items.'Bad'.actionType",
+            "items.'Bad'.targetType",
+            "items.'Bad'.shell",
+            "items.'Bad'.key",
+        ] {
+            assert!(
+                problems.iter().any(|p| p.starts_with(field)),
+                "no problem reported for {field}: {problems:?}"
+            );
+        }
+        assert!(matches!(
+            parse(r#"{"version": 99}"#),
+            Err(SettingsError::UnsupportedVersion(99))
+        ));
+    }
+
+    #[test]
+    fn falls_back_to_last_good_settings() {
+        let dir = std::env::temp_dir().join(format!("tooly-settings-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("settings.json");
+        fs::write(&path, r#"{"order": [], "items": {}, "terminal": "xterm"}"#).unwrap();
+        assert_eq!(or_last_good(load_file(&path)).terminal, "xterm");
+
+        fs::write(&path, r#"{"order": [], "items": {}, "terminal": "nope"}"#).unwrap();
+        let result = load_file(&path);
+        assert!(matches!(result, Err(SettingsError::Invalid(_))));
+        assert_eq!(or_last_good(result).terminal, "xterm");
+        fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,140 @@
+use crate::settings;
+use crate::utils;
+use std::collections::HashMap;
+#[cfg(target_os = "macos")]
+use std::process::Command;
+use std::sync::Mutex;
+use std::thread;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut};
+
+// region: Variables
+
+/// Registered shortcuts (by shortcut id) and the menu item they trigger.
+static BINDINGS: Mutex<Option<HashMap<u32, String>>> = Mutex::new(None);
+
+// endregion
+
+// region: Commands
+
+/// Re-read shortcuts from the settings file and register them again.
+#[tauri::command]
+pub fn reload_shortcuts(app: AppHandle) {
+    register_all(&app);
+}
+
+// endregion
+
+// region: Shortcuts
+
+/// Unregister every shortcut and register the ones defined in settings.
+/// Invalid settings leave the current shortcuts in place.
+pub fn register_all(app: &AppHandle) {
+    let settings = match settings::load(app) {
+        Ok(settings) => settings,
+        Err(e) => {
+            eprintln!("Shortcut - Kept registered shortcuts: {e}");
+            return;
+        }
+    };
+    let shortcuts = app.global_shortcut();
+    shortcuts.unregister_all().ok();
+    let mut bindings = HashMap::new();
+    for (accelerator, id) in &settings.shortcuts {
+        let shortcut = match accelerator.parse::<Shortcut>() {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("Shortcut ({accelerator}) - Invalid accelerator: {e}");
+                continue;
+            }
+        };
+        if !settings.items.contains_key(id) {
+            eprintln!("Shortcut ({accelerator}) - Unknown menu item '{id}'.");
+            continue;
+        }
+        match shortcuts.register(shortcut) {
+            Ok(_) => {
+                bindings.insert(shortcut.id(), id.clone());
+            }
+            Err(e) => eprintln!("Shortcut ({accelerator}) - Failed to register: {e}"),
+        }
+    }
+    println!("Shortcut - Registered {} shortcut(s).", bindings.len());
+    *BINDINGS.lock().unwrap() = Some(bindings);
+}
+
+/// Run the menu item bound to a pressed shortcut on the current selection.
+pub fn dispatch(app: &AppHandle, shortcut: &Shortcut) {
+    let Some(id) = BINDINGS
+        .lock()
+        .unwrap()
+        .as_ref()
+        .and_then(|b| b.get(&shortcut.id()).cloned())
+    else {
+        return;
+    };
+    let app = app.clone();
+    // Selection lookups may block, keep them off the event loop.
+    thread::spawn(move || {
+        let settings = settings::load_or_default(&app);
+        let Some(item) = settings.items.get(&id) else {
+            eprintln!("Shortcut - Menu item '{id}' no longer exists.");
+            return;
+        };
+        if !item.enabled {
+            println!("Shortcut - Menu item '{id}' is disabled.");
+            return;
+        }
+        let (target, items) = current_selection(&app);
+        utils::run_menu_item("shortcut", item, target, items);
+    });
+}
+
+// endregion
+
+// region: Utils
+
+/// Return the current folder and selected items of the file manager.
+/// Only Finder can be queried; elsewhere the home folder is used.
+fn current_selection(app: &AppHandle) -> (String, Vec<String>) {
+    let home = app
+        .path()
+        .home_dir()
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_default();
+    #[cfg(target_os = "macos")]
+    {
+        let script = r#"
+        tell application "Finder"
+            set folderPath to ""
+            try
+                set folderPath to POSIX path of (target of front window as alias)
+            end try
+            set output to folderPath
+            repeat with f in (get selection)
+                try
+                    set output to output & linefeed & POSIX path of (f as alias)
+                end try
+            end repeat
+            return output
+        end tell
+        "#;
+        if let Ok(output) = Command::new("osascript").args(["-e", script]).output() {
+            let result = String::from_utf8_lossy(&output.stdout).to_string();
+            // First line is always the folder (may be empty), the rest is the selection.
+            let mut lines = result.lines();
+            let folder = lines.next().unwrap_or_default().trim_end_matches('/');
+            let selection: Vec<String> = lines
+                .filter(|l| !l.is_empty())
+                .map(|l| l.trim_end_matches('/').to_string())
+                .collect();
+            if !folder.is_empty() {
+                return (folder.to_string(), selection);
+            }
+            return (home, selection);
+        }
+    }
+    (home, Vec::new())
+}
+
+// endregion
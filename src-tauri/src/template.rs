@@ -0,0 +1,167 @@
+use chrono::format::{Item, StrftimeItems};
+use std::collections::HashMap;
+use std::path::Path;
+use tauri_plugin_clipboard_manager::ClipboardExt;
+
+// region: Structs
+
+/// How placeholder values are quoted when inserted.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Quote {
+    /// Inserted as is (file names, file contents, rename patterns).
+    None,
+    /// Single quoted for POSIX shells (bash, zsh, sh).
+    Posix,
+    /// Double quoted for `cmd` batch files, with `%` doubled. Values
+    /// holding `"` or line breaks cannot be quoted and are rejected.
+    Cmd,
+    /// Single quoted for PowerShell.
+    PowerShell,
+    /// String literal for Python.
+    Python,
+    /// Regex replacement, `$` escaped so values are not read as groups.
+    Replacement,
+}
+
+/// Values available to placeholders.
+#[derive(Debug, Clone, Default)]
+pub struct Context {
+    /// Folder the action runs in (`{target}`).
+    pub target: String,
+    /// Item used by `{name}`, `{stem}`, `{ext}` and `{dir}`.
+    pub item: String,
+    /// One based position of the item (`{counter}`).
+    pub counter: usize,
+    /// Values entered by the user, by label (`{prompt:Label}`).
+    pub prompts: HashMap<String, String>,
+}
+
+// endregion
+
+// region: Templates
+
+/// Replace placeholders in `template`:
+///
+/// `{name}` `{stem}` `{ext}` `{dir}` of the item, `{target}` folder,
+/// `{date:%Y-%m-%d}` (strftime, `{date}` alone is `%Y-%m-%d`),
+/// `{counter}` or zero padded `{counter:03}`, `{clipboard}` text and
+/// `{prompt:Label}` values. Unknown braces (`{ a; }`) and `${...}` are
+/// kept, so shell syntax does not need escaping.
+pub fn render(template: &str, context: &Context, quote: Quote) -> Result<String, String> {
+    let mut result = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        result.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        // `${...}` belongs to shells and regex replacements.
+        let value = after
+            .find(['}', '{'])
+            .filter(|end| after.as_bytes()[*end] == b'}' && !result.ends_with('$'))
+            .map(|end| (end, placeholder(&after[..end], context)));
+        match value {
+            Some((end, Some(value))) => {
+                let value = value?;
+                if quote == Quote::Cmd && value.contains(['"', '\r', '\n']) {
+                    return Err(format!(
+                        "Value of '{{{}}}' holds a quote or line break, which cmd cannot quote.",
+                        &after[..end]
+                    ));
+                }
+                result.push_str(&quote_value(&value, quote));
+                rest = &after[end + 1..];
+            }
+            // Not a placeholder, keep the brace and continue after it.
+            _ => {
+                result.push('{');
+                rest = after;
+            }
+        }
+    }
+    result.push_str(rest);
+    Ok(result)
+}
+
+/// Evaluate a placeholder, `None` when the name is not one.
+fn placeholder(inner: &str, context: &Context) -> Option<Result<String, String>> {
+    let (name, argument) = match inner.split_once(':') {
+        Some((name, argument)) => (name, Some(argument)),
+        None => (inner, None),
+    };
+    let path = Path::new(&context.item);
+    let part = |value: Option<&std::ffi::OsStr>| {
+        Ok(value
+            .map(|v| v.to_string_lossy().to_string())
+            .unwrap_or_default())
+    };
+    let value = match (name, argument) {
+        ("name", None) => part(path.file_name()),
+        ("stem", None) => part(path.file_stem()),
+        ("ext", None) => part(path.extension()),
+        ("dir", None) => part(path.parent().map(Path::as_os_str)),
+        ("target", None) => Ok(context.target.clone()),
+        ("date", format) => format_date(format.unwrap_or("%Y-%m-%d")),
+        ("counter", None) => Ok(context.counter.to_string()),
+        ("counter", Some(width)) => match width.parse::<usize>() {
+            Ok(width) => Ok(format!("{:0width$}", context.counter)),
+            Err(_) => Err(format!("Invalid counter width '{width}'.")),
+        },
+        ("clipboard", None) => crate::get_app_handle()
+            .clipboard()
+            .read_text()
+            .map_err(|e| format!("Failed to read clipboard: {e}")),
+        ("prompt", Some(label)) => context
+            .prompts
+            .get(label)
+            .cloned()
+            .ok_or(format!("No value entered for '{label}'.")),
+        _ => return None,
+    };
+    Some(value)
+}
+
+/// Labels of the `{prompt:Label}` placeholders of a template, in order.
+pub fn prompt_labels(template: &str) -> Vec<String> {
+    let mut labels: Vec<String> = Vec::new();
+    let mut rest = template;
+    while let Some(start) = rest.find("{prompt:") {
+        let escaped = rest[..start].ends_with('$');
+        rest = &rest[start + "{prompt:".len()..];
+        let Some(end) = rest.find(['}', '{']) else {
+            break;
+        };
+        let label = &rest[..end];
+        if !escaped && rest.as_bytes()[end] == b'}' && !labels.iter().any(|l| l == label) {
+            labels.push(label.to_string());
+        }
+    }
+    labels
+}
+
+// endregion
+
+// region: Utils
+
+/// Format the current local time, rejecting invalid strftime formats.
+fn format_date(format: &str) -> Result<String, String> {
+    let items: Vec<Item> = StrftimeItems::new(format).collect();
+    if items.iter().any(|item| matches!(item, Item::Error)) {
+        return Err(format!("Invalid date format '{format}'."));
+    }
+    Ok(chrono::Local::now()
+        .format_with_items(items.into_iter())
+        .to_string())
+}
+
+/// Quote a value for the target language.
+pub fn quote_value(value: &str, quote: Quote) -> String {
+    match quote {
+        Quote::None => value.to_string(),
+        Quote::Posix => format!("'{}'", value.replace('\'', "'\\''")),
+        Quote::PowerShell => format!("'{}'", value.replace('\'', "''")),
+        Quote::Cmd => format!("\"{}\"", value.replace('%', "%%")),
+        Quote::Python => serde_json::Value::from(value).to_string(),
+        Quote::Replacement => value.replace('$', "$$"),
+    }
+}
+
+// endregion
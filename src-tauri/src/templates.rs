@@ -0,0 +1,230 @@
+use crate::create;
+use crate::template::{self, Context, Quote};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::{fs, path::Path, path::PathBuf};
+use tauri::{AppHandle, Manager};
+
+// region: Variables
+
+/// Folder of the templates library, in app data.
+const TEMPLATE_FOLDER: &str = "templates";
+
+// endregion
+
+// region: Structs
+
+/// File or folder tree copied by `template:<name>` create actions.
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Template {
+    pub name: String,
+    pub folder: bool,
+    /// Number of files, 1 for single file templates.
+    pub files: usize,
+}
+
+// endregion
+
+// region: Commands
+
+/// Return the templates of the library, sorted by name.
+#[tauri::command]
+pub fn list_templates(app: AppHandle) -> Result<Vec<Template>, String> {
+    list(&app)
+}
+
+/// Copy a file or folder into the library, under its own name unless
+/// `name` is given.
+#[tauri::command]
+pub fn add_template(
+    app: AppHandle,
+    source: String,
+    name: Option<String>,
+) -> Result<Template, String> {
+    let source = Path::new(&source);
+    let name = match name {
+        Some(name) => name,
+        None => source
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .ok_or(format!("Invalid template source '{}'.", source.display()))?,
+    };
+    let path = template_path(&app, &name)?;
+    if path.exists() {
+        return Err(format!("Template '{name}' already exists."));
+    }
+    fs::create_dir_all(templates_dir(&app)).map_err(|e| e.to_string())?;
+    create::copy_tree(source, &path, &mut Vec::new())
+        .map_err(|e| format!("Failed to copy '{}': {e}", source.display()))?;
+    println!("Templates - Added template '{name}'.");
+    Ok(describe(name, &path))
+}
+
+/// Delete a template from the library.
+#[tauri::command]
+pub fn remove_template(app: AppHandle, name: String) -> Result<(), String> {
+    let path = template_path(&app, &name)?;
+    let removed = if path.is_dir() {
+        fs::remove_dir_all(&path)
+    } else {
+        fs::remove_file(&path)
+    };
+    removed.map_err(|e| format!("Failed to remove template '{name}': {e}"))?;
+    println!("Templates - Removed template '{name}'.");
+    Ok(())
+}
+
+// endregion
+
+// region: Templates
+
+/// Split a `template:<name>|<new name>` create action, `None` for plain
+/// `name|content` actions.
+pub fn parse_action(action: &str) -> Option<(&str, &str)> {
+    let spec = action.strip_prefix("template:")?;
+    Some(spec.split_once('|').unwrap_or((spec, "")))
+}
+
+/// Return the templates of the library, sorted by name.
+pub fn list(app: &AppHandle) -> Result<Vec<Template>, String> {
+    let entries = match fs::read_dir(templates_dir(app)) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("Failed to read templates: {e}")),
+    };
+    let mut templates: Vec<Template> = entries
+        .flatten()
+        .map(|entry| {
+            describe(
+                entry.file_name().to_string_lossy().to_string(),
+                &entry.path(),
+            )
+        })
+        .collect();
+    templates.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(templates)
+}
+
+/// Copy a template into `target`, replacing placeholders in file and folder
+/// names and in text contents. The copy is named `name` when not empty and
+/// gets a `(n)` suffix if taken. Returns the created path.
+pub fn instantiate(
+    app: &AppHandle,
+    template: &str,
+    name: &str,
+    target: &Path,
+    context: &Context,
+) -> Result<PathBuf, String> {
+    let source = template_path(app, template)?;
+    if !source.exists() {
+        return Err(format!("Unknown template '{template}'."));
+    }
+    let name = match name {
+        "" => template::render(template, context, Quote::None)?,
+        name => name.to_string(),
+    };
+    let destination = create::unique_path(target, &name)?;
+    let mut created = Vec::new();
+    let result = render_tree(&source, &destination, context, &mut created);
+    create::record_all(created);
+    result.map(|_| destination)
+}
+
+/// Labels of the `{prompt:Label}` placeholders used by a template.
+pub fn prompt_labels(app: &AppHandle, template: &str) -> Vec<String> {
+    let mut labels = template::prompt_labels(template);
+    let Ok(source) = template_path(app, template) else {
+        return labels;
+    };
+    let mut paths = vec![source];
+    while let Some(path) = paths.pop() {
+        if let Ok(entries) = fs::read_dir(&path) {
+            paths.extend(entries.flatten().map(|e| e.path()));
+        } else if let Ok(text) = fs::read_to_string(&path) {
+            labels.extend(template::prompt_labels(&text));
+        }
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        labels.extend(template::prompt_labels(&name));
+    }
+    let mut seen = HashSet::new();
+    labels.retain(|label| seen.insert(label.clone()));
+    labels
+}
+
+/// Copy a file or folder tree, rendering names and text contents.
+fn render_tree(
+    source: &Path,
+    destination: &Path,
+    context: &Context,
+    created: &mut Vec<PathBuf>,
+) -> Result<(), String> {
+    let failed = |e: std::io::Error| format!("Failed to create '{}': {e}", destination.display());
+    if source.is_dir() {
+        fs::create_dir(destination).map_err(failed)?;
+        created.push(destination.to_path_buf());
+        let mut entries: Vec<_> = fs::read_dir(source)
+            .map_err(|e| format!("Failed to read '{}': {e}", source.display()))?
+            .flatten()
+            .collect();
+        entries.sort_by_key(|e| e.file_name());
+        for entry in entries {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let name = template::render(&name, context, Quote::None)?;
+            create::check_file_name(&name)?;
+            render_tree(&entry.path(), &destination.join(name), context, created)?;
+        }
+        return Ok(());
+    }
+    let bytes =
+        fs::read(source).map_err(|e| format!("Failed to read '{}': {e}", source.display()))?;
+    // Binary files are copied as is.
+    let contents = match String::from_utf8(bytes) {
+        Ok(text) => template::render(&text, context, Quote::None)?.into_bytes(),
+        Err(e) => e.into_bytes(),
+    };
+    if destination.exists() {
+        return Err(format!("'{}' already exists.", destination.display()));
+    }
+    fs::write(destination, contents).map_err(failed)?;
+    created.push(destination.to_path_buf());
+    Ok(())
+}
+
+// endregion
+
+// region: Utils
+
+/// Return the library folder.
+fn templates_dir(app: &AppHandle) -> PathBuf {
+    app.path().app_data_dir().unwrap().join(TEMPLATE_FOLDER)
+}
+
+/// Return the path of a template, rejecting names that leave the library.
+fn template_path(app: &AppHandle, name: &str) -> Result<PathBuf, String> {
+    let valid =
+        !name.is_empty() && !matches!(name, "." | "..") && !name.contains(['/', '\\', '\0']);
+    if !valid {
+        return Err(format!("Invalid template name '{name}'."));
+    }
+    Ok(templates_dir(app).join(name))
+}
+
+/// Describe a template of the library.
+fn describe(name: String, path: &Path) -> Template {
+    let mut files = 0;
+    let mut paths = vec![path.to_path_buf()];
+    while let Some(path) = paths.pop() {
+        match fs::read_dir(&path) {
+            Ok(entries) => paths.extend(entries.flatten().map(|e| e.path())),
+            Err(_) => files += 1,
+        }
+    }
+    Template {
+        name,
+        folder: path.is_dir(),
+        files,
+    }
+}
+
+// endregion
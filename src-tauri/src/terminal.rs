@@ -0,0 +1,602 @@
+use crate::settings::{self, ExecMode};
+use crate::template::{quote_value, Quote};
+use crate::utils::Payload;
+use serde::Serialize;
+use std::io::{self, Write};
+#[cfg(unix)]
+use std::os::unix::fs::{DirBuilderExt, MetadataExt, OpenOptionsExt, PermissionsExt};
+#[cfg(not(target_os = "windows"))]
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Command;
+use std::time::Duration;
+use std::{env, fs};
+
+// region: Variables
+
+/// Folder of terminal scripts in the temp folder (with the user id on unix).
+const SCRIPT_FOLDER: &str = "tooly";
+
+/// Scripts older than this are removed on startup.
+const SCRIPT_MAX_AGE: Duration = Duration::from_secs(60 * 60);
+
+/// Item shells a Windows terminal can run, "" is cmd.
+#[cfg(target_os = "windows")]
+const TERMINAL_SHELLS: [&str; 3] = ["", "cmd", "pwsh"];
+
+/// Known terminals, in autodetection order. Each runs the script given
+/// after its command, the script waits for Enter before closing.
+#[cfg(target_os = "linux")]
+static EMULATORS: [Emulator; 6] = [
+    Emulator::new(
+        "gnome-terminal",
+        "GNOME Terminal",
+        "gnome-terminal",
+        &["gnome-terminal", "--"],
+    ),
+    Emulator::new("konsole", "Konsole", "konsole", &["konsole", "-e"]),
+    Emulator::new("kitty", "kitty", "kitty", &["kitty"]),
+    Emulator::new("alacritty", "Alacritty", "alacritty", &["alacritty", "-e"]),
+    Emulator::new("wezterm", "WezTerm", "wezterm", &["wezterm", "start", "--"]),
+    Emulator::new("xterm", "XTerm", "xterm", &["xterm", "-e"]),
+];
+#[cfg(target_os = "macos")]
+static EMULATORS: [Emulator; 2] = [
+    Emulator::new(
+        "terminal",
+        "Terminal",
+        "Terminal",
+        &["open", "-a", "Terminal"],
+    ),
+    Emulator::new("iterm", "iTerm2", "iTerm", &["open", "-a", "iTerm"]),
+];
+#[cfg(target_os = "windows")]
+static EMULATORS: [Emulator; 3] = [
+    Emulator::new("wt", "Windows Terminal", "wt", &["wt"]),
+    Emulator::new("pwsh", "PowerShell", "powershell", &["powershell"]),
+    Emulator::new("cmd", "Command Prompt", "cmd", &["cmd"]),
+];
+
+// endregion
+
+// region: Structs
+
+/// Terminal emulator a terminal action can open.
+struct Emulator {
+    id: &'static str,
+    name: &'static str,
+    /// Program on the `PATH` (app bundle name on macOS).
+    detect: &'static str,
+    /// Program and arguments placed before the script path. Windows
+    /// builds its command lines in `open_windows`.
+    #[cfg_attr(target_os = "windows", allow(dead_code))]
+    command: &'static [&'static str],
+}
+
+/// Terminal shown in the settings.
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TerminalInfo {
+    pub id: String,
+    pub name: String,
+    pub installed: bool,
+}
+
+impl Emulator {
+    const fn new(
+        id: &'static str,
+        name: &'static str,
+        detect: &'static str,
+        command: &'static [&'static str],
+    ) -> Self {
+        Emulator {
+            id,
+            name,
+            detect,
+            command,
+        }
+    }
+}
+
+// endregion
+
+// region: Commands
+
+/// Return the terminals known on this platform and whether they are
+/// installed, for the `terminal` setting.
+#[tauri::command]
+pub fn list_terminals() -> Vec<TerminalInfo> {
+    EMULATORS
+        .iter()
+        .map(|emulator| TerminalInfo {
+            id: emulator.id.to_string(),
+            name: emulator.name.to_string(),
+            installed: installed(emulator.detect),
+        })
+        .collect()
+}
+
+// endregion
+
+// region: Terminal
+
+/// Run terminal command with selected files as arguments.
+pub fn open(info: &Payload) {
+    #[cfg(not(target_os = "windows"))]
+    open_unix(info);
+    #[cfg(target_os = "windows")]
+    open_windows(info);
+}
+
+/// Write the action to a bash script and open it with the default handler.
+#[cfg(not(target_os = "windows"))]
+fn open_unix(info: &Payload) {
+    let script = match info.render_action(Quote::Posix) {
+        Ok(script) => script,
+        Err(e) => {
+            eprintln!("Action (terminal) - {e}");
+            return;
+        }
+    };
+    // Prepare the items as escaped arguments for the shell command.
+    let items = info
+        .items
+        .iter()
+        .map(|s| quote_value(s, Quote::Posix))
+        .collect::<Vec<_>>()
+        .join(" ");
+    // Prompt values are exported, the terminal does not inherit them.
+    let exports: String = info
+        .input_env()
+        .iter()
+        .map(|(name, value)| format!("export {name}={}\n", quote_value(value, Quote::Posix)))
+        .collect();
+    // The script removes itself once bash has it open.
+    let script_content = format!(
+        "#!/bin/bash\n\
+        rm -f -- \"$0\"\n\
+        clear; cd {path}; set -- {args}\n\
+        {exports}\
+        {body}\n\
+        echo \"\nProcess finished. Press Enter to close.\"\n\
+        read; clear",
+        path = quote_value(&info.target, Quote::Posix),
+        args = items,
+        body = terminal_body(info, &script)
+    );
+    let script_path = match create_script("command", &script_content) {
+        Ok(path) => path,
+        Err(e) => {
+            eprintln!("Action (terminal) - Failed to write script: {e}");
+            return;
+        }
+    };
+    let setting = settings::load_or_default(&crate::get_app_handle().clone()).terminal;
+    let mut command = terminal_command(&setting, &script_path);
+    if let Err(e) = command.spawn() {
+        eprintln!("Action (terminal) - Failed to open terminal: {e}");
+    }
+}
+
+/// Command opening a script in the terminal of the `terminal` setting,
+/// falling back to the detected one.
+#[cfg(not(target_os = "windows"))]
+fn terminal_command(setting: &str, script: &Path) -> Command {
+    let chosen = EMULATORS.iter().find(|e| e.id == setting);
+    let emulator = match chosen {
+        Some(emulator) if installed(emulator.detect) => Some(emulator),
+        _ => {
+            if !setting.is_empty() {
+                eprintln!("Action (terminal) - Terminal '{setting}' is not available.");
+            }
+            detect()
+        }
+    };
+    let Some(emulator) = emulator else {
+        // Debian alternatives, then the default handler of the script.
+        let mut command;
+        if cfg!(target_os = "linux") && installed("x-terminal-emulator") {
+            command = Command::new("x-terminal-emulator");
+            command.arg("-e");
+        } else if cfg!(target_os = "linux") {
+            command = Command::new("xdg-open");
+        } else {
+            command = Command::new("open");
+        }
+        command.arg(script);
+        return command;
+    };
+    let mut command = Command::new(emulator.command[0]);
+    command.args(&emulator.command[1..]).arg(script);
+    command
+}
+
+/// Pick a terminal: the default handler on macOS, on Linux the one of the
+/// desktop, then `$TERMINAL`, then the first one installed.
+#[cfg(not(target_os = "windows"))]
+fn detect() -> Option<&'static Emulator> {
+    if cfg!(target_os = "macos") {
+        return None;
+    }
+    let desktop = env::var("XDG_CURRENT_DESKTOP")
+        .unwrap_or_default()
+        .to_lowercase();
+    let preferred = if desktop.contains("kde") {
+        Some("konsole")
+    } else if desktop.contains("gnome") || desktop.contains("unity") {
+        Some("gnome-terminal")
+    } else {
+        None
+    };
+    let from_env = env::var("TERMINAL").ok();
+    let emulator = [preferred, from_env.as_deref()]
+        .into_iter()
+        .flatten()
+        .filter_map(|id| EMULATORS.iter().find(|e| e.id == id))
+        .chain(EMULATORS.iter())
+        .find(|e| installed(e.detect));
+    emulator
+}
+
+/// Write the action to a batch or PowerShell script and run it in a new
+/// console or Windows Terminal tab, depending on the `terminal` setting
+/// (cmd, pwsh or wt, Windows Terminal when available by default). Items
+/// with the pwsh shell always use PowerShell. Scripts remove themselves
+/// once the window is closed.
+#[cfg(target_os = "windows")]
+fn open_windows(info: &Payload) {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NEW_CONSOLE: u32 = 0x0000_0010;
+
+    let terminal = settings::load_or_default(&crate::get_app_handle().clone()).terminal;
+    // Terminals of other platforms fall back to picking one.
+    let terminal = match terminal.as_str() {
+        "wt" | "pwsh" | "cmd" => terminal,
+        _ => String::new(),
+    };
+    if !TERMINAL_SHELLS.contains(&info.options.shell.as_str()) {
+        eprintln!(
+            "Action (terminal) - Shell '{}' is not available in Windows terminals, use cmd or pwsh.",
+            info.options.shell
+        );
+        return;
+    }
+    // The item's shell decides, the terminal setting only without one.
+    let powershell = match info.options.shell.as_str() {
+        "pwsh" => true,
+        "cmd" => false,
+        _ => terminal == "pwsh",
+    };
+    let quote = if powershell {
+        Quote::PowerShell
+    } else {
+        Quote::Cmd
+    };
+    let script = match info.render_action(quote) {
+        Ok(script) => script,
+        Err(e) => {
+            eprintln!("Action (terminal) - {e}");
+            return;
+        }
+    };
+    let created = if powershell {
+        // Windows PowerShell reads scripts without a BOM as ANSI.
+        let content = powershell_script(info, &script);
+        create_script("ps1", &format!("\u{feff}{content}"))
+    } else {
+        match batch_script(info, &script) {
+            Ok(content) => create_script("bat", &content),
+            Err(e) => {
+                eprintln!("Action (terminal) - {e}");
+                return;
+            }
+        }
+    };
+    let script_path = match created {
+        Ok(path) => path,
+        Err(e) => {
+            eprintln!("Action (terminal) - Failed to write script: {e}");
+            return;
+        }
+    };
+    let script_arg = script_path.to_string_lossy().to_string();
+    let (program, args) = if powershell {
+        // PowerShell 7 when installed, Windows PowerShell otherwise.
+        let program = if installed("pwsh") {
+            "pwsh"
+        } else {
+            "powershell"
+        };
+        (
+            program,
+            vec![
+                "-NoLogo",
+                "-ExecutionPolicy",
+                "Bypass",
+                "-File",
+                script_arg.as_str(),
+            ],
+        )
+    } else {
+        ("cmd", vec!["/C", script_arg.as_str()])
+    };
+    let result = if terminal == "wt" || (terminal.is_empty() && installed("wt")) {
+        // Windows Terminal splits its command line on ';'.
+        Command::new("wt")
+            .arg("-d")
+            .arg(info.target.replace(';', "\\;"))
+            .arg(program)
+            .args(args.iter().map(|a| a.replace(';', "\\;")))
+            .spawn()
+    } else {
+        Command::new(program)
+            .args(&args)
+            .current_dir(&info.target)
+            .creation_flags(CREATE_NEW_CONSOLE)
+            .spawn()
+    };
+    match result {
+        Ok(_) => println!("Action (terminal) - Opened '{}'.", script_path.display()),
+        Err(e) => {
+            eprintln!("Action (terminal) - Failed to open terminal: {e}");
+            fs::remove_file(&script_path).ok();
+        }
+    }
+}
+
+// endregion
+
+// region: Scripts
+
+/// Batch file running the action with the items as `%1..%n`. The items are
+/// passed through `call`, which expands `%` once more, so it is doubled
+/// twice. Prompt values holding `"` or line breaks are rejected.
+#[cfg(target_os = "windows")]
+fn batch_script(info: &Payload, script: &str) -> Result<String, String> {
+    let items: Vec<String> = info
+        .items
+        .iter()
+        .map(|item| format!("\"{}\"", item.replace('%', "%%%%")))
+        .collect();
+    // Prompt values are set here, the terminal does not inherit them.
+    let mut exports = String::new();
+    for (name, value) in info.input_env() {
+        if value.contains(['"', '\r', '\n']) {
+            return Err(format!(
+                "Value of '{name}' holds a quote or line break, which cmd cannot quote."
+            ));
+        }
+        exports.push_str(&format!("set \"{name}={}\"\r\n", value.replace('%', "%%")));
+    }
+    Ok(format!(
+        "@echo off\r\n\
+        chcp 65001 >nul\r\n\
+        cls\r\n\
+        cd /d {path}\r\n\
+        {exports}\
+        call :main {items}\r\n\
+        echo.\r\n\
+        echo Process finished. Press any key to close.\r\n\
+        pause >nul\r\n\
+        (goto) 2>nul & del \"%~f0\"\r\n\
+        :main\r\n\
+        {body}\r\n\
+        exit /b\r\n",
+        path = quote_value(&info.target, Quote::Cmd),
+        items = items.join(" "),
+        body = terminal_body(info, script)
+    ))
+}
+
+/// PowerShell script running the action with the items as `$args`. Per-item
+/// modes run items one after the other.
+#[cfg(target_os = "windows")]
+fn powershell_script(info: &Payload, script: &str) -> String {
+    let quote = |value: &str| quote_value(value, Quote::PowerShell);
+    let items: Vec<String> = info.items.iter().map(|item| quote(item)).collect();
+    let exports: String = info
+        .input_env()
+        .iter()
+        .map(|(name, value)| format!("$env:{name} = {}\r\n", quote(value)))
+        .collect();
+    let body = match info.options.mode {
+        ExecMode::Batch => format!("& {{\r\n{script}\r\n}} @items"),
+        ExecMode::Sequential | ExecMode::Parallel => format!(
+            "$ok = 0; $failed = @()\r\n\
+            foreach ($item in $items) {{\r\n\
+            Write-Host \"==> $item\"\r\n\
+            $global:LASTEXITCODE = 0\r\n\
+            & {{\r\n{script}\r\n}} $item\r\n\
+            if ($? -and $LASTEXITCODE -eq 0) {{ $ok++ }} else {{ $failed += $item }}\r\n\
+            }}\r\n\
+            Write-Host \"`n$ok of $($items.Count) item(s) succeeded.\"\r\n\
+            if ($failed) {{ Write-Host \"Failed:\"; $failed | ForEach-Object {{ Write-Host \"  $_\" }} }}"
+        ),
+    };
+    format!(
+        "Clear-Host\r\n\
+        Set-Location -LiteralPath {path}\r\n\
+        {exports}\
+        $items = @({items})\r\n\
+        {body}\r\n\
+        Write-Host \"`nProcess finished. Press Enter to close.\"\r\n\
+        [void](Read-Host)\r\n\
+        Remove-Item -LiteralPath $PSCommandPath -Force\r\n",
+        path = quote(&info.target),
+        items = items.join(", ")
+    )
+}
+
+/// Script lines running the action of a terminal item on the arguments,
+/// once for all of them or once per item depending on its mode.
+#[cfg(not(target_os = "windows"))]
+fn terminal_body(info: &Payload, script: &str) -> String {
+    let summary = "echo; echo \"$ok of $# item(s) succeeded.\"; \
+        [ -n \"$failed\" ] && printf 'Failed:%b\\n' \"$failed\"";
+    match info.options.mode {
+        ExecMode::Batch => script.to_string(),
+        ExecMode::Sequential => format!(
+            "ok=0; failed=\"\"\n\
+            for f in \"$@\"; do\n\
+            echo \"==> $f\"\n\
+            ( set -- \"$f\"; {script}\n\
+            ) && ok=$((ok+1)) || failed=\"$failed\\n  $f\"\n\
+            done\n\
+            {summary}"
+        ),
+        // Keep at most `workers` items running, `jobs` works on bash 3.
+        ExecMode::Parallel => format!(
+            "ok=0; failed=\"\"; i=0\n\
+            for f in \"$@\"; do\n\
+            while [ \"$(jobs -rp | wc -l)\" -ge {workers} ]; do sleep 0.2; done\n\
+            ( set -- \"$f\"; {script}\n\
+            ) & pids[i]=$!; files[i]=\"$f\"; i=$((i+1))\n\
+            done\n\
+            for n in \"${{!pids[@]}}\"; do\n\
+            wait \"${{pids[n]}}\" && ok=$((ok+1)) || failed=\"$failed\\n  ${{files[n]}}\"\n\
+            done\n\
+            {summary}",
+            workers = info.options.worker_limit()
+        ),
+    }
+}
+
+/// Script lines running the action of a terminal item on the arguments.
+/// cmd has no job control, so parallel items run one after the other.
+#[cfg(target_os = "windows")]
+fn terminal_body(info: &Payload, script: &str) -> String {
+    if info.options.mode == ExecMode::Batch {
+        return script.to_string();
+    }
+    format!(
+        "set ok=0\r\n\
+        set failed=0\r\n\
+        :next\r\n\
+        if \"%~1\"==\"\" goto done\r\n\
+        echo ==^> %1\r\n\
+        call :item %1\r\n\
+        shift\r\n\
+        goto next\r\n\
+        :done\r\n\
+        echo. & echo %ok% succeeded, %failed% failed.\r\n\
+        goto finished\r\n\
+        :item\r\n\
+        {script}\r\n\
+        if errorlevel 1 (set /a failed+=1) else (set /a ok+=1)\r\n\
+        exit /b\r\n\
+        :finished"
+    )
+}
+
+// endregion
+
+// region: Utils
+
+/// Check whether a program is on the `PATH` (an app bundle on macOS).
+#[cfg(target_os = "windows")]
+fn installed(program: &str) -> bool {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+    Command::new("where")
+        .arg(program)
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()
+        .is_ok_and(|o| o.status.success())
+}
+
+/// Check whether a program is on the `PATH` (an app bundle on macOS).
+#[cfg(not(target_os = "windows"))]
+fn installed(program: &str) -> bool {
+    if cfg!(target_os = "macos") {
+        let home = env::var("HOME").unwrap_or_default();
+        let bundle = format!("{program}.app");
+        return [
+            "/Applications",
+            "/System/Applications/Utilities",
+            &format!("{home}/Applications"),
+        ]
+        .iter()
+        .any(|dir| Path::new(dir).join(&bundle).exists());
+    }
+    env::var_os("PATH").is_some_and(|paths| {
+        env::split_paths(&paths).any(|dir| {
+            fs::metadata(dir.join(program))
+                .is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+        })
+    })
+}
+
+/// Remove terminal scripts left over by earlier runs.
+pub fn clean_scripts() {
+    let Ok(entries) = scripts_dir().and_then(fs::read_dir) else {
+        return;
+    };
+    // Recent scripts may still be starting in a terminal.
+    for entry in entries.flatten() {
+        let expired = entry
+            .metadata()
+            .and_then(|m| m.modified())
+            .map(|t| t.elapsed().unwrap_or_default() > SCRIPT_MAX_AGE)
+            .unwrap_or(true);
+        if expired {
+            fs::remove_file(entry.path()).ok();
+        }
+    }
+}
+
+/// Write a script under a random name that no other run uses, readable and
+/// executable by the current user only.
+pub fn create_script(extension: &str, content: &str) -> io::Result<PathBuf> {
+    let dir = scripts_dir()?;
+    loop {
+        let mut bytes = [0u8; 8];
+        getrandom::getrandom(&mut bytes).map_err(|e| io::Error::other(e.to_string()))?;
+        let path = dir.join(format!("{}.{extension}", hex::encode(bytes)));
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        options.mode(0o700);
+        match options.open(&path) {
+            Ok(mut file) => {
+                file.write_all(content.as_bytes())?;
+                return Ok(path);
+            }
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Return the folder of terminal scripts, creating it if needed. On unix it
+/// is private to the current user, a folder someone else created is refused.
+fn scripts_dir() -> io::Result<PathBuf> {
+    #[cfg(unix)]
+    let (dir, uid) = {
+        // SAFETY: getuid has no preconditions and cannot fail.
+        let uid = unsafe { libc::getuid() };
+        (env::temp_dir().join(format!("{SCRIPT_FOLDER}-{uid}")), uid)
+    };
+    #[cfg(not(unix))]
+    let dir = env::temp_dir().join(SCRIPT_FOLDER);
+    let mut builder = fs::DirBuilder::new();
+    #[cfg(unix)]
+    builder.mode(0o700);
+    match builder.create(&dir) {
+        Ok(_) => {}
+        Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {}
+        Err(e) => return Err(e),
+    }
+    #[cfg(unix)]
+    {
+        let meta = fs::symlink_metadata(&dir)?;
+        if !meta.is_dir() || meta.uid() != uid || meta.mode() & 0o077 != 0 {
+            return Err(io::Error::other(format!(
+                "'{}' is not a private folder of the current user.",
+                dir.display()
+            )));
+        }
+    }
+    Ok(dir)
+}
+
+// endregion
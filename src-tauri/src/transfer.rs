@@ -0,0 +1,287 @@
+use crate::settings::{self, MenuItem, MenuSettings};
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+use std::{fs, path::Path, path::PathBuf};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons};
+
+// region: Variables
+
+/// Marker identifying Tooly settings bundles.
+const BUNDLE_KIND: &str = "tooly-settings";
+
+// endregion
+
+// region: Structs
+
+/// Portable settings bundle, with home folder paths written as `~`.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Bundle {
+    kind: String,
+    exported_at: u64,
+    settings: MenuSettings,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportMode {
+    /// Add new groups, items and shortcuts, keep local ones on conflict.
+    #[default]
+    Merge,
+    /// Replace local settings entirely.
+    Replace,
+}
+
+#[derive(Debug, Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportReport {
+    /// Item ids added from the bundle.
+    added: Vec<String>,
+    /// Item ids already present with identical content.
+    unchanged: Vec<String>,
+    /// Item ids present locally with different content (local kept).
+    conflicts: Vec<String>,
+    /// Group ids present locally with a different icon (local kept).
+    group_conflicts: Vec<String>,
+    /// Accelerators already bound to another item locally (local kept).
+    shortcut_conflicts: Vec<String>,
+}
+
+// endregion
+
+// region: Commands
+
+/// Export the settings to a portable bundle file.
+#[tauri::command]
+pub fn export_settings(app: AppHandle, path: String) -> Result<(), String> {
+    export(&app, Path::new(&path))
+}
+
+/// Import a settings bundle, merging it or replacing the local settings.
+#[tauri::command]
+pub fn import_settings(
+    app: AppHandle,
+    path: String,
+    mode: Option<ImportMode>,
+) -> Result<ImportReport, String> {
+    import(&app, Path::new(&path), mode.unwrap_or_default())
+}
+
+/// Replace the settings with the built-in defaults.
+#[tauri::command]
+pub fn reset_settings(app: AppHandle) -> Result<(), String> {
+    settings::save(&app, settings::builtin()).map_err(|e| e.to_string())
+}
+
+// endregion
+
+// region: Transfer
+
+/// Write the current settings to `path` as a portable bundle.
+pub fn export(app: &AppHandle, path: &Path) -> Result<(), String> {
+    let mut current = settings::load(app).map_err(|e| e.to_string())?;
+    let home = home_dir(app);
+    for item in current.items.values_mut() {
+        map_paths(item, |p| compact_path(p, &home));
+    }
+    for group in current.groups.values_mut() {
+        group.icon = compact_path(&group.icon, &home);
+    }
+    let bundle = Bundle {
+        kind: BUNDLE_KIND.to_string(),
+        exported_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0),
+        settings: current,
+    };
+    let json = serde_json::to_string_pretty(&bundle).map_err(|e| e.to_string())?;
+    fs::write(path, json).map_err(|e| format!("Failed to write '{}': {e}", path.display()))?;
+    println!("Transfer - Exported settings to '{}'.", path.display());
+    Ok(())
+}
+
+/// Read a bundle from `path` and apply it to the local settings.
+pub fn import(app: &AppHandle, path: &Path, mode: ImportMode) -> Result<ImportReport, String> {
+    let json = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read '{}': {e}", path.display()))?;
+    let mut value: serde_json::Value = serde_json::from_str(&json).map_err(|e| e.to_string())?;
+    if value.get("kind").and_then(|k| k.as_str()) != Some(BUNDLE_KIND) {
+        return Err(format!(
+            "'{}' is not a Tooly settings bundle.",
+            path.display()
+        ));
+    }
+    // Run the bundled settings through the regular migration and validation.
+    let raw = value
+        .get_mut("settings")
+        .map(serde_json::Value::take)
+        .ok_or("Bundle has no settings.")?;
+    let mut incoming = settings::parse(&raw.to_string()).map_err(|e| e.to_string())?;
+    let home = home_dir(app);
+    for item in incoming.items.values_mut() {
+        map_paths(item, |p| expand_path(p, &home));
+    }
+    for group in incoming.groups.values_mut() {
+        group.icon = expand_path(&group.icon, &home);
+    }
+
+    let (merged, report) = match mode {
+        ImportMode::Replace => {
+            let report = ImportReport {
+                added: incoming.items.keys().cloned().collect(),
+                ..ImportReport::default()
+            };
+            (incoming, report)
+        }
+        ImportMode::Merge => merge(settings::load(app).map_err(|e| e.to_string())?, incoming),
+    };
+    settings::save(app, merged).map_err(|e| e.to_string())?;
+    println!(
+        "Transfer - Imported {} item(s), {} conflict(s).",
+        report.added.len(),
+        report.conflicts.len()
+    );
+    Ok(report)
+}
+
+/// Merge incoming settings into local ones, keeping local entries on conflict.
+fn merge(mut local: MenuSettings, incoming: MenuSettings) -> (MenuSettings, ImportReport) {
+    let mut report = ImportReport::default();
+    for (id, group) in incoming.groups {
+        match local.groups.get(&id) {
+            Some(existing) if *existing != group => report.group_conflicts.push(id),
+            Some(_) => {}
+            None => {
+                local.groups.insert(id, group);
+            }
+        }
+    }
+    // Follow the bundle order so added items keep their relative placement.
+    let mut incoming_items = incoming.items;
+    for id in &incoming.order {
+        if id == settings::SEPARATOR {
+            continue;
+        }
+        let Some(item) = incoming_items.remove(id) else {
+            continue;
+        };
+        match local.items.get(id) {
+            Some(existing) if *existing != item => report.conflicts.push(id.clone()),
+            Some(_) => report.unchanged.push(id.clone()),
+            None => {
+                local.items.insert(id.clone(), item);
+                local.order.push(id.clone());
+                report.added.push(id.clone());
+            }
+        }
+    }
+    // Items not listed in the bundle order are added but stay hidden.
+    for (id, item) in incoming_items {
+        match local.items.get(&id) {
+            Some(existing) if *existing != item => report.conflicts.push(id),
+            Some(_) => report.unchanged.push(id),
+            None => {
+                local.items.insert(id.clone(), item);
+                report.added.push(id);
+            }
+        }
+    }
+    for (accelerator, id) in incoming.shortcuts {
+        match local.shortcuts.get(&accelerator) {
+            Some(existing) if *existing != id => report.shortcut_conflicts.push(accelerator),
+            Some(_) => {}
+            None => {
+                local.shortcuts.insert(accelerator, id);
+            }
+        }
+    }
+    (local, report)
+}
+
+/// Handle `tooly://import?file=` after asking the user for confirmation.
+pub fn trigger_import(app: &AppHandle, file: &str) {
+    let app = app.clone();
+    let file = file.to_string();
+    // The confirmation dialog blocks and must not run on the main thread.
+    std::thread::spawn(move || {
+        let confirmed = app
+            .dialog()
+            .message(format!(
+                "Import menu items and shortcuts from '{file}'? Existing items are kept."
+            ))
+            .title("Import Settings")
+            .buttons(MessageDialogButtons::OkCancelCustom(
+                "Import".to_string(),
+                "Cancel".to_string(),
+            ))
+            .blocking_show();
+        if !confirmed {
+            println!("Transfer - Import cancelled by user.");
+            return;
+        }
+        let path = PathBuf::from(expand_path(&file, &home_dir(&app)));
+        let message = match import(&app, &path, ImportMode::Merge) {
+            Ok(report) if report.conflicts.is_empty() && report.shortcut_conflicts.is_empty() => {
+                format!("Imported {} item(s).", report.added.len())
+            }
+            Ok(report) => format!(
+                "Imported {} item(s). Kept local versions of: {}",
+                report.added.len(),
+                report
+                    .conflicts
+                    .iter()
+                    .chain(&report.shortcut_conflicts)
+                    .cloned()
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            Err(e) => format!("Import failed: {e}"),
+        };
+        app.dialog()
+            .message(message)
+            .title("Import Settings")
+            .blocking_show();
+    });
+}
+
+// endregion
+
+// region: Utils
+
+/// Apply `f` to every field of an item that holds a path.
+fn map_paths(item: &mut MenuItem, f: impl Fn(&str) -> String) {
+    if matches!(item.icon_type.as_str(), "app" | "image") {
+        item.icon = f(&item.icon);
+    }
+    if matches!(item.action_type.as_str(), "app" | "shortcut") {
+        item.action = f(&item.action);
+    }
+}
+
+/// Replace a leading home folder with `~`.
+fn compact_path(path: &str, home: &Path) -> String {
+    match Path::new(path).strip_prefix(home) {
+        Ok(rest) if rest.as_os_str().is_empty() => "~".to_string(),
+        Ok(rest) => format!("~/{}", rest.to_string_lossy().replace('\\', "/")),
+        Err(_) => path.to_string(),
+    }
+}
+
+/// Expand a leading `~` to the home folder.
+fn expand_path(path: &str, home: &Path) -> String {
+    match path.strip_prefix('~') {
+        Some("") => home.to_string_lossy().to_string(),
+        Some(rest) if rest.starts_with('/') || rest.starts_with('\\') => {
+            home.join(&rest[1..]).to_string_lossy().to_string()
+        }
+        _ => path.to_string(),
+    }
+}
+
+fn home_dir(app: &AppHandle) -> PathBuf {
+    app.path().home_dir().unwrap_or_default()
+}
+
+// endregion
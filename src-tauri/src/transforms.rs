@@ -0,0 +1,568 @@
+use crate::ansi::{self, Style};
+use crate::jobs;
+use crate::settings::{self, ScriptOptions};
+use crate::template;
+use crate::utils::{self, Payload};
+use base64::engine::general_purpose::{STANDARD, STANDARD_NO_PAD, URL_SAFE, URL_SAFE_NO_PAD};
+use base64::Engine;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::io::{Read, Write};
+use std::process::Stdio;
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+use tauri_plugin_notification::NotificationExt;
+use wait_timeout::ChildExt;
+
+// region: Variables
+
+/// Prefix of actions piping the clipboard through a script.
+const SCRIPT_PREFIX: &str = "script:";
+
+/// Built-in transforms by action name, with the name shown in the tray.
+const TRANSFORMS: [(&str, &str, Transform); 19] = [
+    ("json-pretty", "Format JSON", json_pretty),
+    ("json-minify", "Minify JSON", json_minify),
+    ("base64-encode", "Base64 Encode", base64_encode),
+    ("base64-decode", "Base64 Decode", base64_decode),
+    ("url-encode", "URL Encode", url_encode),
+    ("url-decode", "URL Decode", url_decode),
+    ("upper", "UPPER CASE", |text| Ok(text.to_uppercase())),
+    ("lower", "lower case", |text| Ok(text.to_lowercase())),
+    ("title", "Title Case", title_case),
+    ("sentence", "Sentence case", sentence_case),
+    ("camel", "camelCase", |text| {
+        Ok(map_lines(text, |l| join_words(l, "", true, false)))
+    }),
+    ("pascal", "PascalCase", |text| {
+        Ok(map_lines(text, |l| join_words(l, "", true, true)))
+    }),
+    ("snake", "snake_case", |text| {
+        Ok(map_lines(text, |l| join_words(l, "_", false, false)))
+    }),
+    ("kebab", "kebab-case", |text| {
+        Ok(map_lines(text, |l| join_words(l, "-", false, false)))
+    }),
+    ("constant", "CONSTANT_CASE", |text| {
+        Ok(map_lines(text, |l| {
+            join_words(l, "_", false, false).to_uppercase()
+        }))
+    }),
+    ("trim", "Trim Lines", trim_lines),
+    ("sort", "Sort Lines", sort_lines),
+    ("dedupe", "Remove Duplicate Lines", dedupe_lines),
+    ("strip", "Strip Formatting", strip_formatting),
+];
+
+// endregion
+
+// region: Structs
+
+type Transform = fn(&str) -> Result<String, String>;
+
+/// Built-in transform as listed by the tray window.
+#[derive(Debug, Serialize, Clone)]
+pub struct TransformInfo {
+    pub id: String,
+    pub name: String,
+}
+
+// endregion
+
+// region: Commands
+
+/// Return the built-in clipboard transforms, then the enabled `clipboard`
+/// menu items of the settings (by item id).
+#[tauri::command]
+pub fn list_clipboard_transforms(app: AppHandle) -> Vec<TransformInfo> {
+    let builtin = TRANSFORMS.iter().map(|(id, name, _)| TransformInfo {
+        id: id.to_string(),
+        name: name.to_string(),
+    });
+    let items = settings::load_or_default(&app)
+        .items
+        .into_iter()
+        .filter(|(_, item)| item.enabled && item.action_type == "clipboard")
+        .map(|(id, _)| TransformInfo {
+            name: id.clone(),
+            id,
+        });
+    builtin.chain(items).collect()
+}
+
+/// Apply a built-in transform, or run a `clipboard` menu item of the
+/// settings, on the clipboard text from the tray. Scripts are only taken
+/// from the settings file. Failures are shown as notifications.
+#[tauri::command]
+pub fn run_clipboard_transform(app: AppHandle, id: String) -> Result<(), String> {
+    let home = app
+        .path()
+        .home_dir()
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_default();
+    if TRANSFORMS.iter().any(|(transform, _, _)| *transform == id) {
+        let info = Payload {
+            target: home,
+            target_type: "any".to_string(),
+            items: Vec::new(),
+            action: id,
+            action_type: "clipboard".to_string(),
+            options: ScriptOptions::default(),
+            inputs: Vec::new(),
+            prompts: HashMap::new(),
+        };
+        thread::spawn(move || run(&info));
+        return Ok(());
+    }
+    let item = settings::load_or_default(&app)
+        .items
+        .remove(&id)
+        .filter(|item| item.action_type == "clipboard")
+        .ok_or(format!("Unknown clipboard transform '{id}'."))?;
+    if !item.enabled {
+        return Err(format!("Menu item '{id}' is disabled."));
+    }
+    // Scripts may take a while, keep them off the main thread.
+    thread::spawn(move || utils::run_menu_item("tray", &item, home, Vec::new()));
+    Ok(())
+}
+
+// endregion
+
+// region: Transforms
+
+/// Replace the clipboard text with the result of a `clipboard` action.
+pub fn run(info: &Payload) {
+    let app = crate::get_app_handle().clone();
+    let result = app
+        .clipboard()
+        .read_text()
+        .map_err(|_| "The clipboard holds no text.".to_string())
+        .and_then(|text| apply(info, &text))
+        .and_then(|text| app.clipboard().write_text(text).map_err(|e| e.to_string()));
+    match result {
+        Ok(_) => println!("Action (clipboard) - Applied '{}'.", info.action),
+        Err(e) => {
+            eprintln!(
+                "Action (clipboard) - Failed to apply '{}': {e}",
+                info.action
+            );
+            notify(&app, &e);
+        }
+    }
+}
+
+/// Check that an action names a built-in transform or a script.
+pub fn check_action(action: &str) -> Result<(), String> {
+    if let Some(script) = action.strip_prefix(SCRIPT_PREFIX) {
+        if script.trim().is_empty() {
+            return Err("script cannot be empty".to_string());
+        }
+        return Ok(());
+    }
+    if TRANSFORMS.iter().any(|(id, _, _)| *id == action) {
+        return Ok(());
+    }
+    Err(format!("unknown clipboard transform '{action}'"))
+}
+
+/// Transform text as described by the action.
+fn apply(info: &Payload, text: &str) -> Result<String, String> {
+    if let Some(script) = info.action.strip_prefix(SCRIPT_PREFIX) {
+        return run_script(info, script, text);
+    }
+    let transform = TRANSFORMS
+        .iter()
+        .find(|(id, _, _)| *id == info.action)
+        .map(|(_, _, transform)| transform)
+        .ok_or(format!("Unknown clipboard transform '{}'.", info.action))?;
+    transform(text)
+}
+
+/// Run a script with the text as input and return its output. A trailing
+/// line break is removed when the text had none.
+fn run_script(info: &Payload, script: &str, text: &str) -> Result<String, String> {
+    let script = template::render(script, &info.context(0), jobs::quote(info))?;
+    let (mut command, _script) = jobs::script_command(&Payload {
+        action: script,
+        ..info.clone()
+    })?;
+    command
+        .current_dir(jobs::working_dir(info))
+        .envs(&info.options.env)
+        .envs(info.input_env())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    let mut child = command
+        .spawn()
+        .map_err(|e| format!("Failed to start script: {e}"))?;
+    // Feed and drain the pipes in threads so large texts cannot block.
+    let mut stdin = child.stdin.take();
+    let input = text.to_string();
+    let writer = thread::spawn(move || {
+        if let Some(stdin) = stdin.as_mut() {
+            stdin.write_all(input.as_bytes()).ok();
+        }
+    });
+    let read = |pipe: Option<Box<dyn Read + Send>>| {
+        thread::spawn(move || {
+            let mut output = Vec::new();
+            if let Some(mut pipe) = pipe {
+                pipe.read_to_end(&mut output).ok();
+            }
+            output
+        })
+    };
+    let stdout = read(
+        child
+            .stdout
+            .take()
+            .map(|p| Box::new(p) as Box<dyn Read + Send>),
+    );
+    let stderr = read(
+        child
+            .stderr
+            .take()
+            .map(|p| Box::new(p) as Box<dyn Read + Send>),
+    );
+    let timeout = info.options.timeout.unwrap_or(jobs::SCRIPT_TIMEOUT);
+    let waited = match timeout {
+        0 => child.wait().map(Some),
+        seconds => child.wait_timeout(Duration::from_secs(seconds)),
+    };
+    let status = match waited {
+        Ok(Some(status)) => status,
+        Ok(None) => {
+            jobs::kill_tree(child.id());
+            child.wait().ok();
+            return Err("Script took too long (timeout).".to_string());
+        }
+        Err(e) => return Err(format!("Failed while waiting for script: {e}")),
+    };
+    // Processes left in the background may hold the pipes open.
+    if !jobs::wait_for_pipes(child.id(), || stdout.is_finished() && stderr.is_finished()) {
+        return Err("Script output was not closed.".to_string());
+    }
+    writer.join().ok();
+    let stdout = stdout.join().unwrap_or_default();
+    let stderr = stderr.join().unwrap_or_default();
+    if !status.success() {
+        let stderr = String::from_utf8_lossy(&stderr);
+        return Err(match stderr.lines().rev().find(|l| !l.trim().is_empty()) {
+            Some(line) => line.trim().to_string(),
+            None => format!("Script failed ({status})."),
+        });
+    }
+    let mut output =
+        String::from_utf8(stdout).map_err(|_| "Script output is not text.".to_string())?;
+    if !text.ends_with('\n') {
+        if output.ends_with("\r\n") {
+            output.truncate(output.len() - 2);
+        } else if output.ends_with('\n') {
+            output.pop();
+        }
+    }
+    Ok(output)
+}
+
+fn json_pretty(text: &str) -> Result<String, String> {
+    reformat_json(text, true)
+}
+
+fn json_minify(text: &str) -> Result<String, String> {
+    reformat_json(text, false)
+}
+
+fn base64_encode(text: &str) -> Result<String, String> {
+    Ok(STANDARD.encode(text))
+}
+
+/// Decode standard or url-safe base64, with or without padding.
+fn base64_decode(text: &str) -> Result<String, String> {
+    let text: String = text.chars().filter(|c| !c.is_whitespace()).collect();
+    let bytes = [STANDARD, URL_SAFE, STANDARD_NO_PAD, URL_SAFE_NO_PAD]
+        .iter()
+        .find_map(|engine| engine.decode(&text).ok())
+        .ok_or("The clipboard does not hold valid base64.".to_string())?;
+    String::from_utf8(bytes).map_err(|_| "Decoded data is not text.".to_string())
+}
+
+/// Percent-encode everything but unreserved characters (RFC 3986).
+fn url_encode(text: &str) -> Result<String, String> {
+    Ok(text
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{b:02X}"),
+        })
+        .collect())
+}
+
+fn url_decode(text: &str) -> Result<String, String> {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        let hex = bytes.get(index + 1..index + 3);
+        let value = hex
+            .filter(|_| bytes[index] == b'%')
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match value {
+            Some(value) => {
+                decoded.push(value);
+                index += 3;
+            }
+            None => {
+                decoded.push(bytes[index]);
+                index += 1;
+            }
+        }
+    }
+    String::from_utf8(decoded).map_err(|_| "Decoded data is not text.".to_string())
+}
+
+/// Capitalize the first letter of every word.
+fn title_case(text: &str) -> Result<String, String> {
+    let mut start = true;
+    Ok(text
+        .chars()
+        .map(|c| {
+            let mapped = match start {
+                true => c.to_uppercase().to_string(),
+                false => c.to_lowercase().to_string(),
+            };
+            start = !c.is_alphanumeric() && c != '\'';
+            mapped
+        })
+        .collect())
+}
+
+/// Lower case with the first letter of every sentence capitalized.
+fn sentence_case(text: &str) -> Result<String, String> {
+    let mut start = true;
+    Ok(text
+        .chars()
+        .map(|c| {
+            let mapped = match start && c.is_alphabetic() {
+                true => c.to_uppercase().to_string(),
+                false => c.to_lowercase().to_string(),
+            };
+            if matches!(c, '.' | '!' | '?' | '\n') {
+                start = true;
+            } else if c.is_alphanumeric() {
+                start = false;
+            }
+            mapped
+        })
+        .collect())
+}
+
+/// Trim every line and remove leading and trailing blank lines.
+fn trim_lines(text: &str) -> Result<String, String> {
+    let lines: Vec<&str> = text.lines().map(str::trim).collect();
+    let first = lines.iter().position(|l| !l.is_empty()).unwrap_or(0);
+    let last = lines
+        .iter()
+        .rposition(|l| !l.is_empty())
+        .map_or(0, |i| i + 1);
+    Ok(lines[first..last.max(first)].join(line_ending(text)))
+}
+
+/// Sort lines, ignoring case first and then by the exact text.
+fn sort_lines(text: &str) -> Result<String, String> {
+    let mut lines: Vec<&str> = text.lines().collect();
+    lines.sort_by(|a, b| a.to_lowercase().cmp(&b.to_lowercase()).then(a.cmp(b)));
+    Ok(lines.join(line_ending(text)) + trailing_break(text))
+}
+
+/// Remove repeated lines, keeping the first occurrence.
+fn dedupe_lines(text: &str) -> Result<String, String> {
+    let mut seen = HashSet::new();
+    let lines: Vec<&str> = text.lines().filter(|line| seen.insert(*line)).collect();
+    Ok(lines.join(line_ending(text)) + trailing_break(text))
+}
+
+/// Keep plain text only: rich text formats are dropped when the text is
+/// written back, terminal colors, invisible characters and trailing spaces
+/// are removed, and non-breaking spaces become regular spaces.
+fn strip_formatting(text: &str) -> Result<String, String> {
+    let mut style = Style::default();
+    let lines: Vec<String> = text
+        .lines()
+        .map(|line| {
+            let plain: String = ansi::parse(line, &mut style)
+                .into_iter()
+                .map(|span| span.text)
+                .collect();
+            plain
+                .chars()
+                .filter(|c| !matches!(c, '\u{200B}'..='\u{200D}' | '\u{2060}' | '\u{FEFF}'))
+                .map(|c| match c {
+                    '\u{00A0}' | '\u{202F}' => ' ',
+                    c => c,
+                })
+                .collect::<String>()
+                .trim_end()
+                .to_string()
+        })
+        .collect();
+    Ok(lines.join(line_ending(text)) + trailing_break(text))
+}
+
+// endregion
+
+// region: Utils
+
+/// Re-indent JSON without reordering keys or changing numbers, with two
+/// spaces when `pretty`, or on a single line.
+fn reformat_json(text: &str, pretty: bool) -> Result<String, String> {
+    serde_json::from_str::<serde::de::IgnoredAny>(text)
+        .map_err(|e| format!("The clipboard does not hold valid JSON: {e}"))?;
+    let mut output = String::with_capacity(text.len());
+    let mut depth = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut chars = text.trim().chars().peekable();
+    let newline = |output: &mut String, depth: usize| {
+        if pretty {
+            output.push('\n');
+            output.push_str(&"  ".repeat(depth));
+        }
+    };
+    while let Some(c) = chars.next() {
+        if in_string {
+            output.push(c);
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match c {
+            '"' => {
+                in_string = true;
+                output.push(c);
+            }
+            '{' | '[' => {
+                output.push(c);
+                while chars.peek().is_some_and(|c| c.is_whitespace()) {
+                    chars.next();
+                }
+                // Empty objects and arrays stay on one line.
+                if matches!(chars.peek(), Some('}' | ']')) {
+                    output.extend(chars.next());
+                } else {
+                    depth += 1;
+                    newline(&mut output, depth);
+                }
+            }
+            '}' | ']' => {
+                depth -= 1;
+                newline(&mut output, depth);
+                output.push(c);
+            }
+            ',' => {
+                output.push(c);
+                newline(&mut output, depth);
+            }
+            ':' => output.push_str(if pretty { ": " } else { ":" }),
+            c if c.is_whitespace() => {}
+            c => output.push(c),
+        }
+    }
+    Ok(output)
+}
+
+/// Apply a conversion to every line.
+fn map_lines(text: &str, f: impl Fn(&str) -> String) -> String {
+    let lines: Vec<String> = text.lines().map(f).collect();
+    lines.join(line_ending(text)) + trailing_break(text)
+}
+
+/// Split a line into words on separators and case changes
+/// (`HTTPServer_id` gives `HTTP`, `Server` and `id`), then join them.
+fn join_words(line: &str, separator: &str, capitalize: bool, first_upper: bool) -> String {
+    let chars: Vec<char> = line.chars().collect();
+    let mut words: Vec<String> = Vec::new();
+    let mut word = String::new();
+    for (index, &c) in chars.iter().enumerate() {
+        if !c.is_alphanumeric() {
+            if !word.is_empty() {
+                words.push(std::mem::take(&mut word));
+            }
+            continue;
+        }
+        let previous = index.checked_sub(1).map(|i| chars[i]);
+        let next = chars.get(index + 1);
+        let boundary = c.is_uppercase()
+            && previous.is_some_and(|p| {
+                p.is_lowercase()
+                    || p.is_numeric()
+                    || (p.is_uppercase() && next.is_some_and(|n| n.is_lowercase()))
+            });
+        if boundary && !word.is_empty() {
+            words.push(std::mem::take(&mut word));
+        }
+        word.push(c);
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+        .iter()
+        .enumerate()
+        .map(|(index, word)| {
+            let word = word.to_lowercase();
+            if !capitalize || (index == 0 && !first_upper) {
+                return word;
+            }
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_uppercase().chain(chars).collect())
+                .unwrap_or_default()
+        })
+        .collect::<Vec<String>>()
+        .join(separator)
+}
+
+/// Line ending used by the text.
+fn line_ending(text: &str) -> &'static str {
+    if text.contains("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    }
+}
+
+/// Line break ending the text, if any.
+fn trailing_break(text: &str) -> &'static str {
+    if text.ends_with('\n') {
+        line_ending(text)
+    } else {
+        ""
+    }
+}
+
+/// Show why a transform failed, shortcuts have no other feedback.
+fn notify(app: &AppHandle, message: &str) {
+    let result = app
+        .notification()
+        .builder()
+        .title("Clipboard transform failed")
+        .body(message)
+        .show();
+    if let Err(e) = result {
+        eprintln!("Action (clipboard) - Failed to show notification: {e}");
+    }
+}
+
+// endregion
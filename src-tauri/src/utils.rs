@@ -1,32 +1,83 @@
-use crate::windows;
-use percent_encoding::percent_decode_str;
+use crate::settings::{self, InputField, MenuItem, ScriptOptions};
+use crate::template::{self, Context, Quote};
+use crate::{
+    create, jobs, journal, payload, policy, prompt, rename, terminal, transforms, windows,
+};
 use serde::Deserialize;
-#[cfg(not(target_os = "windows"))]
-use std::os::unix::fs::PermissionsExt;
-use std::process::{Command, Stdio};
+use std::collections::HashMap;
+use std::process::Command;
+use std::sync::Mutex;
 use std::time::Duration;
-use std::{env, fs, path::Path, thread};
+use std::{path::Path, thread};
 use tauri::Url;
-use wait_timeout::ChildExt;
+use tauri_plugin_clipboard_manager::ClipboardExt;
 
 // region: Variables
 
-const SCRIPT_TIMEOUT: u64 = 120;
+/// Time to wait for more `--run` invocations of the same item. Explorer
+/// starts one process per selected file, these are merged into one run.
+const CLI_BATCH_DELAY: Duration = Duration::from_millis(400);
+
+/// Pending `--run` invocations (item id → selected paths).
+static CLI_BATCH: Mutex<Option<HashMap<String, Vec<String>>>> = Mutex::new(None);
 
 // endregion
 
 // region: Structs
 
 #[derive(Debug, Deserialize, Clone)]
-struct Payload {
-    target: String,
+pub struct Payload {
+    pub target: String,
     #[serde(rename = "targetType")]
-    #[allow(dead_code)]
-    target_type: String,
-    items: Vec<String>,
-    action: String,
+    pub target_type: String,
+    pub items: Vec<String>,
+    pub action: String,
     #[serde(rename = "actionType")]
-    action_type: String,
+    pub action_type: String,
+    /// Taken from the local menu item, never from the payload itself.
+    #[serde(skip)]
+    pub options: ScriptOptions,
+    /// Fields of the local menu item, asked for before running.
+    #[serde(skip)]
+    pub inputs: Vec<InputField>,
+    /// Values entered at the prompt, by field name and label.
+    #[serde(skip)]
+    pub prompts: HashMap<String, String>,
+}
+
+impl Payload {
+    /// Placeholder values for the item at `index` (the first one when out of
+    /// range, or the target when nothing is selected).
+    pub fn context(&self, index: usize) -> Context {
+        Context {
+            target: self.target.clone(),
+            item: self
+                .items
+                .get(index)
+                .or(self.items.first())
+                .cloned()
+                .unwrap_or_else(|| self.target.clone()),
+            counter: index + 1,
+            prompts: self.prompts.clone(),
+        }
+    }
+
+    /// Environment variables holding the values of the declared fields.
+    pub fn input_env(&self) -> Vec<(&str, &str)> {
+        self.inputs
+            .iter()
+            .filter_map(|field| {
+                self.prompts
+                    .get(&field.name)
+                    .map(|value| (field.name.as_str(), value.as_str()))
+            })
+            .collect()
+    }
+
+    /// Render the action template with plain values.
+    pub fn render_action(&self, quote: Quote) -> Result<String, String> {
+        template::render(&self.action, &self.context(0), quote)
+    }
 }
 
 // endregion
@@ -36,48 +87,171 @@ struct Payload {
 /// Handle command execution from urls parameters.
 pub fn execute_url(url: &Url) {
     let mut current_command = String::new();
-    let mut current_payload = String::new();
     // Fetch command from url.
     if let Some(command) = url.host_str() {
         current_command = command.to_string();
     }
-    // Fetch payload from url query parameters.
-    if let Some(payload) = url.query_pairs().find(|(k, _)| k == "payload") {
-        current_payload =
-            percent_decode_str(&percent_decode_str(&payload.1.to_string()).decode_utf8_lossy())
-                .decode_utf8_lossy()
-                .to_string();
-    }
     // Perform command specific actions.
     match current_command.as_str() {
-        "run" => run_command(&current_command, &current_payload),
+        "run" => run_command(&current_command, url),
+        "undo" => undo_command(url),
+        "cancel" => cancel_command(url),
+        "import" => import_command(url),
         "uninstall" => crate::uninstall::trigger_uninstall(&crate::get_app_handle()),
         _ => println!("Execution - Unknown command: {}", current_command),
     }
 }
 
-/// Decode instructions from payload and run commands.
-fn run_command(command: &str, payload: &str) {
-    println!(
-        "Command ({}) - Running with payload: '{}'",
-        command, payload
-    );
+/// Handle command line invocations (`--run <item id> <path>`), used by file
+/// manager integrations that cannot open urls. Returns whether the arguments
+/// were handled.
+pub fn execute_args(args: &[String]) -> bool {
+    let Some(index) = args.iter().position(|a| a == "--run") else {
+        return false;
+    };
+    let Some(id) = args.get(index + 1) else {
+        eprintln!("Execution - Missing item id after '--run'.");
+        return true;
+    };
+    let paths = args[index + 2..].to_vec();
+    let mut batch = CLI_BATCH.lock().unwrap();
+    let pending = batch.get_or_insert_with(HashMap::new);
+    if let Some(items) = pending.get_mut(id) {
+        items.extend(paths);
+        return true;
+    }
+    pending.insert(id.clone(), paths);
+    let id = id.clone();
+    thread::spawn(move || {
+        thread::sleep(CLI_BATCH_DELAY);
+        let items = CLI_BATCH
+            .lock()
+            .unwrap()
+            .as_mut()
+            .and_then(|b| b.remove(&id))
+            .unwrap_or_default();
+        let app = crate::get_app_handle().clone();
+        let Some(item) = settings::load_or_default(&app).items.remove(&id) else {
+            eprintln!("Execution - Unknown menu item '{id}'.");
+            return;
+        };
+        if !item.enabled {
+            eprintln!("Execution - Menu item '{id}' is disabled.");
+            return;
+        }
+        // Files run in their folder, a lone folder background runs in itself.
+        let target = match items.first() {
+            Some(first)
+                if items.len() == 1 && Path::new(first).is_dir() && !item_selects(&item) =>
+            {
+                first.clone()
+            }
+            Some(first) => Path::new(first)
+                .parent()
+                .map(|p| p.to_string_lossy().to_string())
+                .unwrap_or_else(|| first.clone()),
+            None => String::new(),
+        };
+        let payload = menu_payload(&item, target, items);
+        if let Err(e) = policy::check(&app, &payload) {
+            eprintln!("Execution - Denied menu item '{id}': {e}");
+            return;
+        }
+        run_payload("cli", &payload);
+    });
+    true
+}
 
-    let info = match load_payload(payload) {
+/// Verify the signed payload of a url and run its action.
+fn run_command(command: &str, url: &Url) {
+    let app = crate::get_app_handle().clone();
+    let mut info = match payload::decode(&app, url) {
         Ok(p) => p,
         Err(e) => {
-            eprintln!("Command ({}) - Failed to load payload: {e}", command);
+            eprintln!("Command ({}) - Rejected request '{}': {e}", command, url);
             return;
         }
     };
+    let command = command.to_string();
+    // Confirmation dialogs block and must not run on the main thread.
+    thread::spawn(move || {
+        match policy::check(&app, &info) {
+            Ok(Some(item)) => {
+                info.options = item.options;
+                info.inputs = item.inputs;
+            }
+            Ok(None) => {}
+            Err(e) => {
+                eprintln!(
+                    "Command ({}) - Denied '{}' action: {e}",
+                    command, info.action_type
+                );
+                return;
+            }
+        }
+        println!(
+            "Command ({}) - Running '{}' on {} item(s).",
+            command,
+            info.action_type,
+            info.items.len()
+        );
+        run_payload(&command, &info);
+    });
+}
+
+/// Run a menu item from settings on the given folder and selection.
+pub fn run_menu_item(command: &str, item: &MenuItem, target: String, items: Vec<String>) {
+    run_payload(command, &menu_payload(item, target, items));
+}
+
+/// Build the payload running a menu item on the selected items.
+fn menu_payload(item: &MenuItem, target: String, mut items: Vec<String>) -> Payload {
+    // Nothing selected anywhere: act on the current folder.
+    if items.is_empty() {
+        items = vec![target.clone()];
+    }
+    Payload {
+        target,
+        target_type: item.target_type.clone(),
+        items,
+        action: item.action.clone(),
+        action_type: item.action_type.clone(),
+        options: item.options.clone(),
+        inputs: item.inputs.clone(),
+        prompts: HashMap::new(),
+    }
+}
 
+/// Run the menu action described by a payload, asking for its inputs
+/// first. Must not be called from the main thread.
+pub fn run_payload(command: &str, info: &Payload) {
+    if let Err(e) = info.options.check() {
+        eprintln!("Command ({}) - Invalid options: {e}", command);
+        return;
+    }
+    let info = &match prompt::collect(info) {
+        Ok(Some(prompts)) => Payload {
+            prompts,
+            ..info.clone()
+        },
+        Ok(None) => {
+            println!("Command ({}) - Cancelled at the prompt.", command);
+            return;
+        }
+        Err(e) => {
+            eprintln!("Command ({}) - Failed to ask for inputs: {e}", command);
+            return;
+        }
+    };
     match info.action_type.as_str() {
-        "create" => action_create(&info),
-        "app" => action_app(&info, false),
-        "shortcut" => action_app(&info, true),
-        "terminal" => action_terminal(&info),
-        "script" => async_action_script(&info),
-        "replace" => action_find_and_replace(&info),
+        "create" => action_create(info),
+        "app" => action_app(info, false),
+        "shortcut" => action_app(info, true),
+        "terminal" => terminal::open(info),
+        "script" => jobs::spawn(info),
+        "replace" => action_find_and_replace(info),
+        "copy" => action_copy(info),
+        "clipboard" => transforms::run(info),
         _ => {
             eprintln!(
                 "Command ({}) - Unknown action type: {}",
@@ -87,67 +261,83 @@ fn run_command(command: &str, payload: &str) {
     }
 }
 
+/// Revert the last operations from the journal after confirmation
+/// (`tooly://undo?count=N`).
+fn undo_command(url: &Url) {
+    let count = url
+        .query_pairs()
+        .find(|(k, _)| k == "count")
+        .and_then(|(_, v)| v.parse().ok())
+        .unwrap_or(1);
+    journal::trigger_undo(&crate::get_app_handle(), count);
+}
+
+/// Stop a queued or running script (`tooly://cancel?job=N`).
+fn cancel_command(url: &Url) {
+    let job = url
+        .query_pairs()
+        .find(|(k, _)| k == "job")
+        .and_then(|(_, v)| v.parse().ok());
+    match job.map(jobs::cancel) {
+        Some(Ok(_)) => {}
+        Some(Err(e)) => eprintln!("Command (cancel) - {e}"),
+        None => eprintln!("Command (cancel) - Missing or invalid 'job' parameter."),
+    }
+}
+
+/// Import a settings bundle (`tooly://import?file=<path>`).
+fn import_command(url: &Url) {
+    match url.query_pairs().find(|(k, _)| k == "file") {
+        Some((_, file)) => crate::transfer::trigger_import(&crate::get_app_handle(), &file),
+        None => eprintln!("Command (import) - Missing 'file' parameter."),
+    }
+}
+
 // endregion
 
 // region: Menu Actions
 
-// Create file with name and content.
+/// Create files, folders or copies, then reveal the result.
 fn action_create(info: &Payload) {
-    let parts: Vec<&str> = info.action.split('|').collect();
-    let file_name = parts.get(0).unwrap_or(&"New File.txt");
-    let file_content = parts.get(1).unwrap_or(&"");
-
-    let target_path = Path::new(&info.target);
-    let mut new_file_path = target_path.join(file_name);
-    let mut counter = 1;
-
-    while new_file_path.exists() {
-        let file_stem = target_path
-            .join(Path::new(file_name).file_stem().unwrap())
-            .to_string_lossy()
-            .to_string();
-        let extension = Path::new(file_name)
-            .extension()
-            .map(|ext| format!(".{}", ext.to_string_lossy()))
-            .unwrap_or_default();
-        new_file_path = target_path.join(format!("{} ({}){}", file_stem, counter, extension));
-        counter += 1;
-    }
-
-    match fs::write(&new_file_path, file_content) {
-        Ok(_) => println!(
-            "Action (create) - Created file '{}' in '{}'.",
-            new_file_path.file_name().unwrap().to_string_lossy(),
-            target_path.display()
-        ),
-        Err(e) => eprintln!(
-            "Action (create) - Failed to create file in '{}': {}",
-            target_path.display(),
-            e
-        ),
+    match create::create(info) {
+        Ok(path) => {
+            println!(
+                "Action (create) - Created '{}' in '{}'.",
+                path.file_name().unwrap_or_default().to_string_lossy(),
+                info.target
+            );
+            reveal(&path);
+        }
+        Err(e) => eprintln!("Action (create) - {e}"),
     }
-    // TODO: Add support for windows & linux.
 }
 
 /// Run selected files with app.
 fn action_app(info: &Payload, is_shortcut: bool) {
+    let app = match info.render_action(Quote::None) {
+        Ok(app) => app,
+        Err(e) => {
+            eprintln!("Action (app) - {e}");
+            return;
+        }
+    };
     let mut command;
     #[cfg(target_os = "macos")]
     {
         command = Command::new("open");
-        command.arg("-a").arg(&info.action);
+        command.arg("-a").arg(&app);
     }
 
     #[cfg(target_os = "windows")]
     {
         command = Command::new("cmd");
-        command.args(["/C", "start", "", &info.action]);
+        command.args(["/C", "start", "", &app]);
     }
 
     #[cfg(target_os = "linux")]
     {
         command = Command::new("xdg-open");
-        command.arg(&info.action);
+        command.arg(&app);
     }
 
     if !is_shortcut {
@@ -158,172 +348,79 @@ fn action_app(info: &Payload, is_shortcut: bool) {
     }
 
     match command.spawn() {
-        Ok(_) => println!("Action (app) - Launched app '{}'", info.action),
+        Ok(_) => println!("Action (app) - Launched app '{}'", app),
         Err(e) => eprintln!("Action (app) - Failed to launch app: {e}"),
     }
 }
 
-/// Run terminal command with selected files as arguments.
-fn action_terminal(info: &Payload) {
-    // Create a temporary script file.
-    let temp_dir = env::temp_dir();
-    let script_path = temp_dir.join("tooly.command");
-
-    // Execute terminal for MacOS or Linux.
-    #[cfg(not(target_os = "windows"))]
-    {
-        // Prepare the items as escaped arguments for the shell command.
-        let items = info
-            .items
-            .iter()
-            .map(|s| format!("'{}'", s.replace("'", "'\\''")))
-            .collect::<Vec<_>>()
-            .join(" ");
-        // Prepare the script content.
-        let script_content = format!(
-            "#!/bin/bash\n\
-            clear; cd \"{path}\"; set -- {args}; {script}\n\
-            echo \"\nProcess finished. Press Enter to close.\"\n\
-            read; clear",
-            path = info.target,
-            args = items,
-            script = info.action
-        );
-        // Write script to file.
-        fs::write(&script_path, script_content).ok();
-        // Allow file to be executable.
-        fs::set_permissions(&script_path, fs::Permissions::from_mode(0o755)).ok();
-        #[cfg(target_os = "macos")]
-        {
-            // Execute script with default MacOS application or terminal.
-            Command::new("open").arg(&script_path).spawn().ok();
-        }
-        #[cfg(target_os = "linux")]
-        {
-            // Execute script with default Linux application or terminal.
-            Command::new("xdg-open").arg(&script_path).spawn().ok();
-        }
-    }
-
-    // Execute terminal for Windows.
-    #[cfg(target_os = "windows")]
-    {
-        // Prepare the items as separate arguments for the shell command.
-        let items: Vec<&str> = info.items.iter().map(|s| s.as_str()).collect();
-        // Prepare the script content.
-        let script_content = format!(
-            "@echo off\r\n\
-            cls & cd /d \"{path}\" & {script}\r\n\
-            echo Process finished. Press Enter to close.\r\n\
-            pause >nul & cls\r\n",
-            path = info.target,
-            script = info.action
-        );
-        // Write script to file.
-        fs::write(&script_path, script_content).ok();
-        // Execute script with default Windows application or terminal.
-        let script_path_str = script_path.to_string_lossy().to_string();
-        let mut cmd_args = vec!["/C", "start", "cmd", "/K", &script_path_str];
-        cmd_args.extend(items);
-        Command::new("cmd").args(cmd_args).spawn().ok();
+/// Copy selected files paths to the clipboard.
+fn action_copy(info: &Payload) {
+    let paths = info.items.join("\n");
+    match crate::get_app_handle().clipboard().write_text(paths) {
+        Ok(_) => println!("Action (copy) - Copied {} path(s).", info.items.len()),
+        Err(e) => eprintln!("Action (copy) - Failed to copy paths: {e}"),
     }
 }
 
-/// Execute script with selected file as arguments.
-fn async_action_script(info: &Payload) {
-    // Run the script in a separate thread to avoid blocking & hanging.
-    let info_clone: Payload = info.clone();
-    thread::spawn(move || {
-        action_script(&info_clone);
-    });
+/// Open the find and replace window for the selected files names.
+fn action_find_and_replace(info: &Payload) {
+    rename::set_selection(&info.items);
+    windows::open_find_and_replace();
 }
 
-/// Execute script with selected file as arguments with timeout protection and non-blocking execution.
-fn action_script(info: &Payload) {
-    let mut command;
-    // Start cmd script with arguments from info..
+// endregion
+
+// region: Utils
+
+/// Show a file or folder selected in the file manager.
+pub fn reveal(path: &Path) {
+    #[cfg(target_os = "macos")]
+    let result = Command::new("open").arg("-R").arg(path).spawn();
+
     #[cfg(target_os = "windows")]
-    {
-        command = Command::new("cmd");
-        command
-            .arg("/C")
-            .arg(&info.action)
-            .stdin(Stdio::null())
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped());
+    let result = {
+        use std::os::windows::process::CommandExt;
+        // Explorer parses its own command line, `/select,` must not be quoted.
+        Command::new("explorer")
+            .raw_arg(format!("/select,\"{}\"", path.display()))
+            .spawn()
+    };
 
-        for item in &info.items {
-            command.arg(item);
-        }
-    }
-    // Start bash script with arguments from info.
-    #[cfg(not(target_os = "windows"))]
-    {
-        command = Command::new("bash");
-        command
-            .arg("--noprofile")
-            .arg("--norc")
-            .arg("-c")
-            .arg(&info.action)
-            .arg("--")
-            .stdin(Stdio::null())
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped());
-        for item in &info.items {
-            command.arg(item);
-        }
-    }
-    // Start script execution.
-    let mut child = match command.spawn() {
-        Ok(c) => c,
-        Err(e) => {
-            eprintln!("Command (script) - Failed to execute script: {e}");
+    // Ask the file manager over D-Bus, or open the parent folder.
+    #[cfg(target_os = "linux")]
+    let result = {
+        let shown = Url::from_file_path(path).ok().is_some_and(|uri| {
+            Command::new("dbus-send")
+                .args([
+                    "--session",
+                    "--print-reply",
+                    "--dest=org.freedesktop.FileManager1",
+                    "--type=method_call",
+                    "/org/freedesktop/FileManager1",
+                    "org.freedesktop.FileManager1.ShowItems",
+                ])
+                .arg(format!("array:string:{uri}"))
+                .arg("string:")
+                .output()
+                .is_ok_and(|o| o.status.success())
+        });
+        if shown {
             return;
         }
+        Command::new("xdg-open")
+            .arg(path.parent().unwrap_or(path))
+            .spawn()
     };
-    // Start timeout protection to avoid hanging processes and ensure non-blocking execution.
-    let timeout = Duration::from_secs(SCRIPT_TIMEOUT);
-    match child.wait_timeout(timeout) {
-        // Execution if it finished in time.
-        Ok(Some(_status)) => {
-            // Command exited → collect output
-            let output = child.wait_with_output().unwrap();
-            let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
-            let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
-            if !stdout.is_empty() {
-                println!("Command (script) - Output: {}", stdout);
-            }
-            if !stderr.is_empty() {
-                eprintln!("Command (script) - Error: {}", stderr);
-            }
-        }
-        // Execution if timed out.
-        Ok(None) => {
-            // Kill the process and its children to prevent hanging.
-            child.kill().ok();
-            eprintln!("Command (script) - Execution took too long (timeout).");
-        }
-        // Execution if an error occurred while waiting.
-        Err(e) => {
-            eprintln!("Command (script) - Failed while waiting for command: {e}");
-        }
-    }
-}
 
-/// Find and replace in selected files names.
-fn action_find_and_replace(_info: &Payload) {
-    // TODO: Implement find and replace in file names for macOS, windows and linux.
-    windows::open_find_and_replace();
+    if let Err(e) = result {
+        eprintln!("Execution - Failed to reveal '{}': {e}", path.display());
+    }
 }
 
-// endregion
-
-// region: Utils
-
-fn load_payload(path: &str) -> Result<Payload, Box<dyn std::error::Error>> {
-    let json = fs::read_to_string(Path::new(path))?;
-    let payload: Payload = serde_json::from_str(&json)?;
-    Ok(payload)
+/// Check whether an item is meant for selected folders rather than the
+/// folder being browsed.
+fn item_selects(item: &MenuItem) -> bool {
+    item.target_type == "folder"
 }
 
 // endregion
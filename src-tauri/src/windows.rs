@@ -1,6 +1,6 @@
 use crate::get_app_handle;
 use tauri::{
-    AppHandle, Manager, WebviewUrl, WebviewWindow, WebviewWindowBuilder, Wry,
+    AppHandle, Emitter, Manager, WebviewUrl, WebviewWindow, WebviewWindowBuilder, Wry,
 };
 #[cfg(target_os = "macos")]
 use tauri::TitleBarStyle;
@@ -51,6 +51,33 @@ pub fn open_find_and_replace() {
     let _window = create("far", "Find and Replace", "index.html", |config| config);
 }
 
+pub fn open_log(job: u64) {
+    let exists = get_app_handle().get_webview_window("log").is_some();
+    let url = format!("index.html?view=log&job={job}");
+    let window = create("log", "Script Log", &url, |config| {
+        config.inner_size(720.0, 440.0).min_inner_size(360.0, 200.0)
+    });
+    // An open log window switches to the new job.
+    if exists {
+        window.emit("log-show", job).ok();
+    }
+}
+
+pub fn open_prompt() {
+    let exists = get_app_handle().get_webview_window("prompt").is_some();
+    let window = create("prompt", "Tooly", "index.html?view=prompt", |config| {
+        config
+            .inner_size(380.0, 320.0)
+            .resizable(false)
+            .always_on_top(true)
+            .skip_taskbar(true)
+    });
+    // A hidden prompt window reloads its fields.
+    if exists {
+        window.emit("prompt-show", ()).ok();
+    }
+}
+
 // endregion
 
 // region: Utils
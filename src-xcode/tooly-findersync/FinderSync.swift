@@ -4,6 +4,7 @@ import Cocoa
 import FinderSync
 import Foundation
 import AppKit
+import CryptoKit
 
 class FinderSync: FIFinderSync {
     
@@ -372,44 +373,71 @@ class FinderSync: FIFinderSync {
         pasteboard.setString(paths, forType: .string)
     }
     
-    func writePayload(_ menuItem: MenuItem, _ items: [URL], _ target: URL?) throws {
-        let payload = Payload(
-            actionType: menuItem.actionType,
-            action: menuItem.action,
-            targetType: menuItem.targetType,
-            items: items.map(\.path),
-            target: target?.path
-            )
-        let url = SettingsManager.shared.payloadFile
-        let data = try JSONEncoder().encode(payload)
-        try data.write(to: url, options: .atomic)
-    }
-    
+    /// Payloads longer than this are stored under a one-time token instead.
+    private let inlineLimit = 8000
+
+    /// Open `tooly://run` with the payload inline (base64url) or referenced by
+    /// a token, signed with the key shared with the app. Inline payloads are
+    /// signed with their issue time and a nonce so they cannot be replayed.
     func signalMainApp(_ menuItem: MenuItem, _ items: [URL], _ target: URL? = nil)
     {
         do {
-            try writePayload(
-                menuItem,
-                items,
-                target
-            )
+            guard let key = SettingsManager.shared.signingKey() else { return }
+            let payload = Payload(
+                actionType: menuItem.actionType,
+                action: menuItem.action,
+                targetType: menuItem.targetType,
+                items: items.map(\.path),
+                target: target?.path
+                )
+            let data = try JSONEncoder().encode(payload)
+            let inline = data.base64EncodedString()
+                .replacingOccurrences(of: "+", with: "-")
+                .replacingOccurrences(of: "/", with: "_")
+                .replacingOccurrences(of: "=", with: "")
+
             var components = URLComponents()
             components.scheme = "tooly"
             components.host = "run"
-            components.queryItems = [
-                URLQueryItem(
-                    name: "payload",
-                    value: SettingsManager.shared.payloadFile.path()
-                )
-            ]
-            
+            if inline.count <= inlineLimit {
+                let issued = String(Int(Date().timeIntervalSince1970))
+                let nonce = UUID().uuidString
+                components.queryItems = [
+                    URLQueryItem(name: "payload", value: inline),
+                    URLQueryItem(name: "ts", value: issued),
+                    URLQueryItem(name: "nonce", value: nonce),
+                    URLQueryItem(name: "sig", value: sign("\(issued):\(nonce):\(inline)", key)),
+                ]
+            } else {
+                let token = UUID().uuidString
+                let folder = SettingsManager.shared.tokensFolder
+                try FileManager.default.createDirectory(
+                    at: folder,
+                    withIntermediateDirectories: true,
+                    attributes: nil)
+                try data.write(
+                    to: folder.appendingPathComponent("\(token).json"),
+                    options: .atomic)
+                components.queryItems = [
+                    URLQueryItem(name: "token", value: token),
+                    URLQueryItem(name: "sig", value: sign(token, key)),
+                ]
+            }
+
             guard let url = components.url else { return }
             NSWorkspace.shared.open(url)
-            print(url.path())
         }
         catch {
             print("Signal - Failed to send payload:", error)
         }
     }
+
+    /// Hex HMAC-SHA256 of a message.
+    func sign(_ message: String, _ key: String) -> String {
+        let code = HMAC<SHA256>.authenticationCode(
+            for: Data(message.utf8),
+            using: SymmetricKey(data: Data(key.utf8)))
+        return code.map { String(format: "%02x", $0) }.joined()
+    }
     
 }
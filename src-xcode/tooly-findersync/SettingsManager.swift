@@ -5,6 +5,7 @@ import Foundation
 // MARK: - Structs
 
 struct MenuSettings: Codable {
+    let version: Int?
     let order: [String]
     let groups: [String: MenuGroup]
     let items: [String: MenuItem]
@@ -47,7 +48,8 @@ class SettingsManager {
     private(set) var itemOrder: [String] = []
     private(set) var separators: Bool = true
     private(set) var settingsFile: URL
-    private(set) var payloadFile: URL
+    private(set) var keyFile: URL
+    private(set) var tokensFolder: URL
     private var source: DispatchSourceFileSystemObject?
     private let appFolder: String = "Tooly"
 
@@ -70,7 +72,8 @@ class SettingsManager {
             attributes: nil)
         
         settingsFile = appSupportURL.appendingPathComponent("settings.json")
-        payloadFile = appSupportURL.appendingPathComponent("payload.json")
+        keyFile = appSupportURL.appendingPathComponent("secret.key")
+        tokensFolder = appSupportURL.appendingPathComponent("tokens")
         print("Init - Fetched settings URL: " + settingsFile.path())
         loadSettings()
         watchSettings()
@@ -93,20 +96,43 @@ class SettingsManager {
         }
     }
 
+    /// Read the key shared with the app, used to sign payloads.
+    func signingKey() -> String? {
+        guard let key = try? String(contentsOf: keyFile, encoding: .utf8) else {
+            print("Sign - Failed to read signing key")
+            return nil
+        }
+        return key.trimmingCharacters(in: .whitespacesAndNewlines)
+    }
+
     /// Start watching the settings file for changes.
+    /// The app saves settings atomically (write + rename), which replaces the
+    /// watched file, so the watcher is re-armed on delete and rename events.
     func watchSettings() {
         print("Watch - Started watching settings file")
+        source?.cancel()
+        source = nil
         let fileDescriptor = open(settingsFile.path, O_EVTONLY)
-        guard fileDescriptor != -1 else { return }
+        guard fileDescriptor != -1 else {
+            // File may be mid-replace, try again shortly.
+            DispatchQueue.global().asyncAfter(deadline: .now() + 1) { [weak self] in
+                self?.watchSettings()
+            }
+            return
+        }
 
         source = DispatchSource.makeFileSystemObjectSource(
             fileDescriptor: fileDescriptor,
-            eventMask: .write,
+            eventMask: [.write, .delete, .rename],
             queue: DispatchQueue.global()
         )
 
         source?.setEventHandler { [weak self] in
-            self?.loadSettings()
+            guard let self = self, let source = self.source else { return }
+            if source.data.contains(.delete) || source.data.contains(.rename) {
+                self.watchSettings()
+            }
+            self.loadSettings()
         }
 
         source?.setCancelHandler {
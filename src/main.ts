@@ -1,7 +1,15 @@
 import "./style.css";
+import { showPrompt } from "./prompt";
 
-document.querySelector<HTMLDivElement>("#app")!.innerHTML = `
+const app = document.querySelector<HTMLDivElement>("#app")!;
+const view = new URLSearchParams(window.location.search).get("view");
+
+if (view === "prompt") {
+  showPrompt(app);
+} else {
+  app.innerHTML = `
   <div>
     <h1>This is the main page</h1>
   </div>
 `;
+}
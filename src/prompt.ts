@@ -0,0 +1,134 @@
+import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+import { open } from "@tauri-apps/plugin-dialog";
+
+type InputKind = "text" | "number" | "choice" | "checkbox" | "file";
+
+interface InputField {
+  name: string;
+  label?: string;
+  kind: InputKind;
+  default?: string;
+  choices?: string[];
+}
+
+interface PromptRequest {
+  items: string[];
+  fields: InputField[];
+}
+
+// region: Rendering
+
+function fieldInput(field: InputField): HTMLElement {
+  const value = field.default ?? "";
+  switch (field.kind) {
+    case "choice": {
+      const select = document.createElement("select");
+      for (const choice of field.choices ?? []) {
+        select.add(new Option(choice, choice, false, choice === value));
+      }
+      return select;
+    }
+    case "checkbox": {
+      const input = document.createElement("input");
+      input.type = "checkbox";
+      input.checked = value === "true";
+      return input;
+    }
+    case "file": {
+      const row = document.createElement("div");
+      row.className = "file";
+      const input = document.createElement("input");
+      input.value = value;
+      const browse = document.createElement("button");
+      browse.type = "button";
+      browse.textContent = "…";
+      browse.addEventListener("click", async () => {
+        const path = await open({ multiple: false, directory: false });
+        if (typeof path === "string") input.value = path;
+      });
+      row.append(input, browse);
+      return row;
+    }
+    default: {
+      const input = document.createElement("input");
+      input.type = field.kind === "number" ? "number" : "text";
+      input.step = "any";
+      input.value = value;
+      return input;
+    }
+  }
+}
+
+function fieldValue(field: InputField, element: HTMLElement): string {
+  if (field.kind === "checkbox") {
+    return String((element as HTMLInputElement).checked);
+  }
+  const input = element.querySelector("input") ?? element;
+  return (input as HTMLInputElement | HTMLSelectElement).value;
+}
+
+async function render(root: HTMLElement) {
+  const request = await invoke<PromptRequest | null>("get_prompt");
+  root.replaceChildren();
+  if (!request) return;
+
+  const form = document.createElement("form");
+  form.className = "prompt";
+  if (request.items.length > 0) {
+    const items = document.createElement("p");
+    items.className = "items";
+    items.textContent = request.items.join(", ");
+    form.append(items);
+  }
+
+  const inputs = request.fields.map((field) => {
+    const label = document.createElement("label");
+    label.textContent = field.label || field.name;
+    const input = fieldInput(field);
+    label.append(input);
+    form.append(label);
+    return input;
+  });
+
+  const error = document.createElement("p");
+  error.className = "error";
+  const cancel = document.createElement("button");
+  cancel.type = "button";
+  cancel.textContent = "Cancel";
+  cancel.addEventListener("click", () => invoke("submit_prompt", { values: null }));
+  const ok = document.createElement("button");
+  ok.type = "submit";
+  ok.textContent = "OK";
+  const buttons = document.createElement("div");
+  buttons.className = "buttons";
+  buttons.append(cancel, ok);
+  form.append(error, buttons);
+
+  form.addEventListener("submit", async (event) => {
+    event.preventDefault();
+    const values: Record<string, string> = {};
+    request.fields.forEach((field, i) => (values[field.name] = fieldValue(field, inputs[i])));
+    try {
+      await invoke("submit_prompt", { values });
+      error.textContent = "";
+    } catch (e) {
+      error.textContent = String(e);
+    }
+  });
+  form.addEventListener("keydown", (event) => {
+    if (event.key === "Escape") invoke("submit_prompt", { values: null });
+  });
+
+  root.append(form);
+  form.querySelector<HTMLElement>("input, select")?.focus();
+}
+
+// endregion
+
+/** Show the inputs of the action waiting on the prompt window. */
+export function showPrompt(root: HTMLElement) {
+  render(root);
+  // The window is reused for later prompts.
+  listen("prompt-show", () => render(root));
+}